@@ -0,0 +1,120 @@
+use crate::binary_mask::MainHashtables;
+use crate::{ChessBoard, TypePiece};
+
+impl ChessBoard {
+    // mask of the pieces of color attacking index if the occupied squares were board
+    // board differing from self.board allows to look through pieces (x-rays, moving king, ...)
+    pub(crate) fn get_attackers(
+        &self,
+        ma: &MainHashtables,
+        index: u8,
+        color: usize,
+        board: u64,
+    ) -> u64 {
+        let index = index as usize;
+        let offset = if color == 1 { 0 } else { 6 };
+        let queens = self.pieces[TypePiece::WhiteQueen as usize + offset];
+        let rooks = self.pieces[TypePiece::WhiteRook as usize + offset];
+        let bishops = self.pieces[TypePiece::WhiteBishop as usize + offset];
+        let knights = self.pieces[TypePiece::WhiteKnight as usize + offset];
+        let pawns = self.pieces[TypePiece::WhitePawn as usize + offset];
+        let king = self.pieces[TypePiece::WhiteKing as usize + offset];
+
+        (ma.get_rook_attacks(index, board) & (queens | rooks))
+            | (ma.get_bishop_attacks(index, board) & (queens | bishops))
+            | (ma.knight_move_masks[index] & knights)
+            | (ma.king_move_masks[index] & king)
+            // a pawn of color attacks index if a pawn of the other color on index would attack it
+            | (ma.pawn_mask_takes_hashmaps[color][index] & pawns)
+    }
+
+    // mask of all the pieces of the given side attacking the square index
+    pub fn attackers_to(&self, ma: &MainHashtables, index: u8, by_white: bool) -> u64 {
+        self.get_attackers(ma, index, by_white as usize, self.board)
+    }
+
+    pub fn is_square_attacked(&self, ma: &MainHashtables, index: u8, by_white: bool) -> bool {
+        self.attackers_to(ma, index, by_white) != 0
+    }
+
+    pub fn is_in_check(&self, ma: &MainHashtables) -> bool {
+        let king = if self.is_white_to_play {
+            TypePiece::WhiteKing
+        } else {
+            TypePiece::BlackKing
+        };
+        let king_index = self.pieces[king as usize].trailing_zeros() as u8;
+        self.is_square_attacked(ma, king_index, !self.is_white_to_play)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::square_to_index;
+
+    fn sq(square: &str) -> u8 {
+        square_to_index(square).unwrap() as u8
+    }
+
+    fn bit(square: &str) -> u64 {
+        1 << sq(square)
+    }
+
+    #[test]
+    fn sliders_are_blocked_and_seen_through() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let board = ChessBoard::from_fen("7k/8/8/3p4/8/8/Q5B1/R3K3 w - - 0 1").unwrap();
+
+        // the rook is behind the queen and the bishop is blocked by the d5 pawn
+        assert_eq!(board.attackers_to(&ma, sq("a8"), true), bit("a2"));
+
+        // removing the blockers from the occupancy reveals the x-rays
+        let without_queen = board.board & !bit("a2");
+        assert_eq!(
+            board.get_attackers(&ma, sq("a8"), 1, without_queen),
+            bit("a1") | bit("a2")
+        );
+        let without_pawn = board.board & !bit("d5");
+        assert_eq!(
+            board.get_attackers(&ma, sq("a8"), 1, without_pawn),
+            bit("a2") | bit("g2")
+        );
+
+        assert!(!board.is_square_attacked(&ma, sq("a8"), false));
+    }
+
+    #[test]
+    fn pawns_attack_forward() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let board = ChessBoard::from_fen("4k3/8/8/4p3/4P3/8/8/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(board.attackers_to(&ma, sq("d5"), true), bit("e4"));
+        assert_eq!(board.attackers_to(&ma, sq("f5"), true), bit("e4"));
+        assert!(!board.is_square_attacked(&ma, sq("e5"), true));
+        assert!(!board.is_square_attacked(&ma, sq("d3"), true));
+        assert!(!board.is_square_attacked(&ma, sq("f3"), true));
+
+        assert_eq!(board.attackers_to(&ma, sq("d4"), false), bit("e5"));
+        assert_eq!(board.attackers_to(&ma, sq("f4"), false), bit("e5"));
+        assert!(!board.is_square_attacked(&ma, sq("e4"), false));
+        assert!(!board.is_square_attacked(&ma, sq("d6"), false));
+        assert!(!board.is_square_attacked(&ma, sq("f6"), false));
+    }
+
+    #[test]
+    fn kings_and_knights_attack() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let board = ChessBoard::from_fen("4k3/8/8/8/8/5n2/8/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(board.attackers_to(&ma, sq("e1"), false), bit("f3"));
+        assert!(board.is_in_check(&ma));
+
+        assert_eq!(board.attackers_to(&ma, sq("d2"), true), bit("e1"));
+        assert_eq!(board.attackers_to(&ma, sq("d2"), false), bit("f3"));
+        assert_eq!(board.attackers_to(&ma, sq("e2"), true), bit("e1"));
+        assert!(!board.is_square_attacked(&ma, sq("e2"), false));
+        assert!(!board.is_square_attacked(&ma, sq("e3"), true));
+    }
+}
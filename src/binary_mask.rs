@@ -1,7 +1,67 @@
+use std::fmt::Write;
+
+use crate::magic_constants::{BISHOP_MAGIC_ENTRIES, ROOK_MAGIC_ENTRIES};
+use crate::zobrist::next_random;
+
+// seed of the magic numbers search used by default, the same seed always gives the same tables
+pub const DEFAULT_MAGIC_SEED: u64 = 0x706f6d6d65;
+
+// where the magic numbers of the slider tables come from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MagicSource {
+    // the ones of magic_constants.rs, nearly instant
+    Precomputed,
+    // searched at runtime with a random generator seeded by the given seed
+    Search(u64),
+}
+
+// how the index in slider_attacks is computed from the occupancy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SliderBackend {
+    // multiplication by the magic number and shift
+    Magic,
+    // pext instruction of bmi2, only on x86-64 cpus supporting it
+    Pext,
+}
+
+impl SliderBackend {
+    pub fn is_available(self) -> bool {
+        match self {
+            SliderBackend::Magic => true,
+            #[cfg(target_arch = "x86_64")]
+            SliderBackend::Pext => std::is_x86_feature_detected!("bmi2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            SliderBackend::Pext => false,
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+fn pext(board: u64, mask: u64) -> u64 {
+    std::arch::x86_64::_pext_u64(board, mask)
+}
+
+// never used as the pext backend isn't available outside of x86-64, kept so the code compiles
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn pext(board: u64, mut mask: u64) -> u64 {
+    let mut result = 0;
+    let mut bit = 0;
+    while mask != 0 {
+        let index = mask.trailing_zeros();
+        result |= ((board >> index) & 1) << bit;
+        bit += 1;
+        mask &= mask - 1;
+    }
+    result
+}
+
 pub struct MainHashtables {
-    pub rook_mask_blockers_hashmaps: Vec<Vec<Option<u64>>>,
+    // attacks of the rooks then of the bishops, each square owns the part starting at the offset
+    // of its magic entry, ordered according to slider_backend
+    pub slider_attacks: Vec<u64>,
+    pub(crate) slider_backend: SliderBackend,
     pub rook_moves_masks_magical_numbers: [MagicEntry; 64],
-    pub bishop_mask_blockers_hashmaps: Vec<Vec<Option<u64>>>,
     pub bishop_moves_masks_magical_numbers: [MagicEntry; 64],
     pub knight_move_masks: [u64; 64],
     pub pawn_mask_takes_hashmaps: [[u64; 64]; 2], // pawn_mask_takes_hashmaps[color][square]
@@ -13,15 +73,84 @@ pub struct MainHashtables {
     pub king_move_masks: [u64; 64],
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct MagicEntry {
     pub mask: u64,
     pub magic_number: u64,
+    // 64 - the number of squares of mask, so the hashkey has exactly one bit by square
+    pub shift: u8,
+    pub offset: u32,
+}
+
+impl MagicEntry {
+    fn get_index(&self, board: u64) -> usize {
+        let hashkey = (board & self.mask).wrapping_mul(self.magic_number) >> self.shift;
+        self.offset as usize + hashkey as usize
+    }
+}
+
+impl MainHashtables {
+    fn get_slider_index(&self, magic_entry: &MagicEntry, board: u64) -> usize {
+        match self.slider_backend {
+            SliderBackend::Magic => magic_entry.get_index(board),
+            // safe as the backend can only be selected if the cpu supports bmi2
+            SliderBackend::Pext => {
+                magic_entry.offset as usize + unsafe { pext(board, magic_entry.mask) } as usize
+            }
+        }
+    }
+
+    // squares attacked by a rook on index, the first blocker in each direction is included
+    pub fn get_rook_attacks(&self, index: usize, board: u64) -> u64 {
+        self.slider_attacks
+            [self.get_slider_index(&self.rook_moves_masks_magical_numbers[index], board)]
+    }
+
+    // squares attacked by a bishop on index, the first blocker in each direction is included
+    pub fn get_bishop_attacks(&self, index: usize, board: u64) -> u64 {
+        self.slider_attacks
+            [self.get_slider_index(&self.bishop_moves_masks_magical_numbers[index], board)]
+    }
+
+    pub fn get_slider_backend(&self) -> SliderBackend {
+        self.slider_backend
+    }
+
+    // reorders slider_attacks for the given backend, which must be available
+    pub fn set_slider_backend(&mut self, slider_backend: SliderBackend) {
+        assert!(
+            slider_backend.is_available(),
+            "{:?} isn't supported by this cpu",
+            slider_backend
+        );
+        self.slider_attacks = get_slider_attacks(
+            &self.rook_moves_masks_magical_numbers,
+            &self.bishop_moves_masks_magical_numbers,
+            slider_backend,
+        )
+        .expect("the magic numbers don't fit the slider attacks");
+        self.slider_backend = slider_backend;
+    }
+
+    // squares strictly between from_index and to_index if they share a line, 0 otherwise
+    pub fn get_between_mask(&self, from_index: usize, to_index: usize) -> u64 {
+        let from_mask = 1 << from_index;
+        let to_mask = 1 << to_index;
+        let rook_attacks = self.get_rook_attacks(from_index, to_mask);
+        if rook_attacks & to_mask != 0 {
+            return rook_attacks & self.get_rook_attacks(to_index, from_mask);
+        }
+        let bishop_attacks = self.get_bishop_attacks(from_index, to_mask);
+        if bishop_attacks & to_mask != 0 {
+            return bishop_attacks & self.get_bishop_attacks(to_index, from_mask);
+        }
+        0
+    }
 }
 
 pub fn get_rook_moves_masks() -> [u64; 64] {
     let mut moves: [u64; 64] = [0; 64];
-    for i in 0..64 {
+    for (i, square_moves) in moves.iter_mut().enumerate() {
         let mut rook_moves = 0;
         let x = i % 8;
         let y = i / 8;
@@ -34,7 +163,7 @@ pub fn get_rook_moves_masks() -> [u64; 64] {
                 rook_moves |= 1 << (x + j * 8);
             }
         }
-        moves[i] = rook_moves;
+        *square_moves = rook_moves;
     }
     moves
 }
@@ -75,65 +204,20 @@ pub fn get_rook_moves_masks_collision(index: usize, mask: &u64) -> u64 {
 }
 
 pub fn get_rook_moves_masks_magical_numbers(
-    mask_blockers_hashmaps: &mut Vec<Vec<Option<u64>>>,
+    slider_attacks: &mut Vec<u64>,
+    random_state: &mut u64,
 ) -> [MagicEntry; 64] {
-    assert!(mask_blockers_hashmaps.len() == 64 && mask_blockers_hashmaps[0].len() == 65536);
-    let mut magical_numbers: [Option<MagicEntry>; 64] = [const { None }; 64];
-    let moves_masks = get_rook_moves_masks();
-    for (i, moves_mask) in moves_masks.iter().enumerate() {
-        // get mask indexes
-        let mut mask_indexes: [u8; 12] = [0; 12];
-        for (j, index) in (0..64).filter(|x| 1 << x & moves_mask != 0).enumerate() {
-            assert!(j < 12);
-            mask_indexes[j] = index;
-        }
-
-        // get mask blockers
-        let mut mask_blockers: [u64; 4096] = [0; 4096];
-        for j in 0..4096 {
-            let mut mask_blocker = 0;
-            for (k, index) in mask_indexes.iter().enumerate() {
-                if 1 << k & j != 0 {
-                    mask_blocker |= 1 << index;
-                }
-            }
-            mask_blockers[j] = mask_blocker;
-        }
-
-        // find magic number
-        loop {
-            let j = rand::random_range(0..=18446744073709551615)
-                & rand::random_range(0..=18446744073709551615)
-                & rand::random_range(0..=18446744073709551615);
-            let mut is_valid = true;
-            for mask_blocker in mask_blockers.iter() {
-                let hashkey = mask_blocker.wrapping_mul(j) >> 48;
-                let colision = get_rook_moves_masks_collision(i, mask_blocker);
-                if mask_blockers_hashmaps[i][hashkey as usize].is_some_and(|x| x != colision) {
-                    is_valid = false;
-                    break;
-                }
-                mask_blockers_hashmaps[i][hashkey as usize] = Some(colision);
-            }
-            if is_valid {
-                magical_numbers[i] = Some(MagicEntry {
-                    mask: *moves_mask,
-                    magic_number: j,
-                });
-                break;
-            }
-            // reset mask_blockers_hashmaps
-            for j in 0..65536 {
-                mask_blockers_hashmaps[i][j] = None;
-            }
-        }
-    }
-    magical_numbers.map(|x| x.unwrap())
+    get_moves_masks_magical_numbers(
+        slider_attacks,
+        &get_rook_moves_masks(),
+        get_rook_moves_masks_collision,
+        random_state,
+    )
 }
 
 pub fn get_bishop_moves_masks() -> [u64; 64] {
     let mut moves: [u64; 64] = [0; 64];
-    for i in 0..64 {
+    for (i, square_moves) in moves.iter_mut().enumerate() {
         let mut bishop_moves = 0;
         let x = i % 8;
         let y = i / 8;
@@ -152,7 +236,7 @@ pub fn get_bishop_moves_masks() -> [u64; 64] {
                 bishop_moves |= 1 << ((y - j) * 8 + x + j);
             }
         }
-        moves[i] = bishop_moves;
+        *square_moves = bishop_moves;
     }
     moves
 }
@@ -165,105 +249,184 @@ pub fn get_bishop_moves_masks_collision(index: usize, mask: &u64) -> u64 {
     let mut i = 1;
     while x >= i && y >= i {
         let square_index = (y - i) * 8 + x - i;
+        collision_mask |= 1 << square_index;
         if 1 << square_index & mask != 0 {
             break;
         }
-        collision_mask |= 1 << square_index;
         i += 1;
     }
     // top right
     let mut i = 1;
     while x + i <= 7 && y >= i {
         let square_index = (y - i) * 8 + x + i;
+        collision_mask |= 1 << square_index;
         if 1 << square_index & mask != 0 {
             break;
         }
-        collision_mask |= 1 << square_index;
         i += 1;
     }
     // bottom left
     let mut i = 1;
     while x >= i && y + i <= 7 {
         let square_index = (y + i) * 8 + x - i;
+        collision_mask |= 1 << square_index;
         if 1 << square_index & mask != 0 {
             break;
         }
-        collision_mask |= 1 << square_index;
         i += 1;
     }
-    // top right
+    // bottom right
     let mut i = 1;
     while x + i <= 7 && y + i <= 7 {
         let square_index = (y + i) * 8 + x + i;
+        collision_mask |= 1 << square_index;
         if 1 << square_index & mask != 0 {
             break;
         }
-        collision_mask |= 1 << square_index;
         i += 1;
     }
     collision_mask
 }
 
 pub fn get_bishop_moves_masks_magical_numbers(
-    mask_blockers_hashmaps: &mut Vec<Vec<Option<u64>>>,
+    slider_attacks: &mut Vec<u64>,
+    random_state: &mut u64,
 ) -> [MagicEntry; 64] {
-    assert!(mask_blockers_hashmaps.len() == 64 && mask_blockers_hashmaps[0].len() == 65536);
-    let mut magical_numbers: [Option<MagicEntry>; 64] = [const { None }; 64];
-    let moves_masks = get_bishop_moves_masks();
-    for (i, moves_mask) in moves_masks.iter().enumerate() {
-        // get mask indexes
-        let mut mask_indexes: [u8; 13] = [0; 13];
-        for (j, index) in (0..64).filter(|x| 1 << x & moves_mask != 0).enumerate() {
-            assert!(j < 13);
-            mask_indexes[j] = index;
-        }
-
-        // get mask blockers
-        let mut mask_blockers: [u64; 4096] = [0; 4096];
-        for j in 0..4096 {
-            let mut mask_blocker = 0;
-            for (k, index) in mask_indexes.iter().enumerate() {
-                if 1 << k & j != 0 {
-                    mask_blocker |= 1 << index;
-                }
-            }
-            mask_blockers[j] = mask_blocker;
+    get_moves_masks_magical_numbers(
+        slider_attacks,
+        &get_bishop_moves_masks(),
+        get_bishop_moves_masks_collision,
+        random_state,
+    )
+}
+
+// every subset of the squares of mask
+fn get_mask_blockers(mask: u64) -> Vec<u64> {
+    let mut mask_blockers = Vec::with_capacity(1 << mask.count_ones());
+    let mut mask_blocker: u64 = 0;
+    loop {
+        mask_blockers.push(mask_blocker);
+        mask_blocker = mask_blocker.wrapping_sub(mask) & mask;
+        if mask_blocker == 0 {
+            return mask_blockers;
         }
+    }
+}
+
+// fills the attacks of a square, collisions[i] being the attacks with mask_blockers[i] as blockers
+// returns false if two blockers with different attacks get the same hashkey
+// attacks must be filled with 0, a slider always attacks at least one square so 0 means unused
+fn fill_slider_attacks(
+    attacks: &mut [u64],
+    magic_entry: &MagicEntry,
+    mask_blockers: &[u64],
+    collisions: &[u64],
+) -> bool {
+    for (mask_blocker, colision) in mask_blockers.iter().zip(collisions) {
+        let hashkey = mask_blocker.wrapping_mul(magic_entry.magic_number) >> magic_entry.shift;
+        let attack = &mut attacks[hashkey as usize];
+        if *attack != 0 && *attack != *colision {
+            return false;
+        }
+        *attack = *colision;
+    }
+    true
+}
+
+// the attacks of each square are appended to slider_attacks
+fn get_moves_masks_magical_numbers(
+    slider_attacks: &mut Vec<u64>,
+    moves_masks: &[u64; 64],
+    get_collision: fn(usize, &u64) -> u64,
+    random_state: &mut u64,
+) -> [MagicEntry; 64] {
+    let mut magical_numbers = [MagicEntry {
+        mask: 0,
+        magic_number: 0,
+        shift: 0,
+        offset: 0,
+    }; 64];
+    for (i, moves_mask) in moves_masks.iter().enumerate() {
+        let mask_blockers = get_mask_blockers(*moves_mask);
+        let collisions: Vec<u64> = (mask_blockers.iter())
+            .map(|mask_blocker| get_collision(i, mask_blocker))
+            .collect();
+        let offset = slider_attacks.len();
+        slider_attacks.resize(offset + mask_blockers.len(), 0);
+        let attacks = &mut slider_attacks[offset..];
 
         // find magic number
         loop {
-            let j = rand::random_range(0..=18446744073709551615)
-                & rand::random_range(0..=18446744073709551615)
-                & rand::random_range(0..=18446744073709551615);
-            let mut is_valid = true;
-            for mask_blocker in mask_blockers.iter() {
-                let hashkey = mask_blocker.wrapping_mul(j) >> 48;
-                let colision = get_bishop_moves_masks_collision(i, mask_blocker);
-                if mask_blockers_hashmaps[i][hashkey as usize].is_some_and(|x| x != colision) {
-                    is_valid = false;
-                    break;
-                }
-                mask_blockers_hashmaps[i][hashkey as usize] = Some(colision);
+            let magic_number =
+                next_random(random_state) & next_random(random_state) & next_random(random_state);
+            // the top bits of the hashkey have to depend on enough squares to have a chance
+            if (moves_mask.wrapping_mul(magic_number) >> 56).count_ones() < 6 {
+                continue;
             }
-            if is_valid {
-                magical_numbers[i] = Some(MagicEntry {
-                    mask: *moves_mask,
-                    magic_number: j,
-                });
+            let magic_entry = MagicEntry {
+                mask: *moves_mask,
+                magic_number,
+                shift: 64 - moves_mask.count_ones() as u8,
+                offset: offset as u32,
+            };
+            if fill_slider_attacks(attacks, &magic_entry, &mask_blockers, &collisions) {
+                magical_numbers[i] = magic_entry;
                 break;
             }
-            // reset mask_blockers_hashmaps
-            for j in 0..65536 {
-                mask_blockers_hashmaps[i][j] = None;
+            attacks.fill(0);
+        }
+    }
+    magical_numbers
+}
+
+// attacks of the rooks then of the bishops ordered for slider_backend, None if an offset doesn't
+// follow the attacks of the previous square or if a magic number gives the same hashkey to two
+// blockers with different attacks
+pub(crate) fn get_slider_attacks(
+    rook_magic_entries: &[MagicEntry; 64],
+    bishop_magic_entries: &[MagicEntry; 64],
+    slider_backend: SliderBackend,
+) -> Option<Vec<u64>> {
+    let mut slider_attacks = Vec::new();
+    for (magic_entries, get_collision) in [
+        (
+            rook_magic_entries,
+            get_rook_moves_masks_collision as fn(usize, &u64) -> u64,
+        ),
+        (bishop_magic_entries, get_bishop_moves_masks_collision),
+    ] {
+        for (i, magic_entry) in magic_entries.iter().enumerate() {
+            let offset = slider_attacks.len();
+            if magic_entry.offset as usize != offset {
+                return None;
+            }
+            let mask_blockers = get_mask_blockers(magic_entry.mask);
+            let collisions =
+                (mask_blockers.iter()).map(|mask_blocker| get_collision(i, mask_blocker));
+            match slider_backend {
+                SliderBackend::Magic => {
+                    let collisions: Vec<u64> = collisions.collect();
+                    slider_attacks.resize(offset + mask_blockers.len(), 0);
+                    if !fill_slider_attacks(
+                        &mut slider_attacks[offset..],
+                        magic_entry,
+                        &mask_blockers,
+                        &collisions,
+                    ) {
+                        return None;
+                    }
+                }
+                // the blockers are enumerated in the order of their pext index
+                SliderBackend::Pext => slider_attacks.extend(collisions),
             }
         }
     }
-    magical_numbers.map(|x| x.unwrap())
+    Some(slider_attacks)
 }
 
 pub fn get_knight_moves_masks() -> [u64; 64] {
     let mut moves: [u64; 64] = [0; 64];
-    for i in 0..64 {
+    for (i, square_moves) in moves.iter_mut().enumerate() {
         let mut knight_moves = 0;
         let x: i32 = i as i32 % 8;
         let y: i32 = i as i32 / 8;
@@ -279,66 +442,69 @@ pub fn get_knight_moves_masks() -> [u64; 64] {
             (2, -1),
         ] {
             if 0 <= x + xa && x + xa < 8 && 0 <= y + ya && y + ya < 8 {
-                knight_moves |= 1 << (y + ya) * 8 + x + xa;
+                knight_moves |= 1 << ((y + ya) * 8 + x + xa);
             }
         }
-        moves[i] = knight_moves;
+        *square_moves = knight_moves;
     }
     moves
 }
 
 pub fn get_pawn_takes_hashmaps() -> [[u64; 64]; 2] {
     let mut result = [[0; 64]; 2];
-    for i in 0..64 {
+    // white
+    for (i, mask) in result[1].iter_mut().enumerate() {
         let x = i % 8;
         let y = i / 8;
-        // white
-        let mut mask = 0;
         if x > 0 && y != 7 {
-            mask |= 1 << (i + 7);
+            *mask |= 1 << (i + 7);
         }
         if x < 7 && y != 7 {
-            mask |= 1 << (i + 9);
+            *mask |= 1 << (i + 9);
         }
-        result[1][i] = mask;
-        // black
-        let mut mask = 0;
+    }
+    // black
+    for (i, mask) in result[0].iter_mut().enumerate() {
+        let x = i % 8;
+        let y = i / 8;
         if x < 7 && y != 0 {
-            mask |= 1 << (i - 7);
+            *mask |= 1 << (i - 7);
         }
         if x > 0 && y != 0 {
-            mask |= 1 << (i - 9);
+            *mask |= 1 << (i - 9);
         }
-        result[0][i] = mask;
     }
     result
 }
 
 pub fn get_pawn_mask_blockers_hashmaps() -> [[[u64; 4]; 64]; 2] {
     let mut result = [[[0; 4]; 64]; 2];
-    for i in 0..64 {
+    // white
+    for (i, mask_blockers) in result[0].iter_mut().enumerate() {
         let y = i / 8;
-        // white
         if y > 0 {
-            result[0][i][0] = 1 << (i - 8);
-            result[0][i][1] = 0;
+            mask_blockers[0] = 1 << (i - 8);
+            mask_blockers[1] = 0;
         }
         if y == 6 {
-            result[0][i][0] = (1 << (i - 8)) | (1 << (i - 16));
-            result[0][i][1] = 0;
-            result[0][i][2] = 1 << (i - 8);
-            result[0][i][3] = 0;
+            mask_blockers[0] = (1 << (i - 8)) | (1 << (i - 16));
+            mask_blockers[1] = 1 << (i - 8);
+            mask_blockers[2] = 0;
+            mask_blockers[3] = 0;
         }
-        // black
+    }
+    // black
+    for (i, mask_blockers) in result[1].iter_mut().enumerate() {
+        let y = i / 8;
         if y < 7 {
-            result[1][i][0] = 1 << (i + 8);
-            result[1][i][2] = 0;
+            mask_blockers[0] = 1 << (i + 8);
+            mask_blockers[2] = 0;
         }
         if y == 1 {
-            result[1][i][0] = (1 << (i + 8)) | (1 << (i + 16));
-            result[1][i][2] = 0;
-            result[1][i][1] = 1 << (i + 8);
-            result[1][i][3] = 0;
+            mask_blockers[0] = (1 << (i + 8)) | (1 << (i + 16));
+            mask_blockers[2] = 0;
+            mask_blockers[1] = 1 << (i + 8);
+            mask_blockers[3] = 0;
         }
     }
     result
@@ -346,29 +512,36 @@ pub fn get_pawn_mask_blockers_hashmaps() -> [[[u64; 4]; 64]; 2] {
 
 pub fn get_pawn_offsets() -> [[[u8; 2]; 64]; 2] {
     let mut result = [[[0; 2]; 64]; 2];
-    for i in 0..64 {
+    // white
+    for (i, offsets) in result[0].iter_mut().enumerate() {
         let i2 = i as u8;
-        // white
         if i > 8 {
-            result[0][i][0] = i2 - 9;
-            result[0][i][1] = i2 - 9;
+            offsets[0] = i2 - 9;
+            offsets[1] = i2 - 9;
+        } else if i == 8 {
+            // the square in front is the bit 0, a shift of i - 9 isn't possible
+            offsets[0] = 0;
+            offsets[1] = 0;
         } else {
-            result[0][i][0] = i2;
-            result[0][i][1] = i2;
+            offsets[0] = i2;
+            offsets[1] = i2;
         }
         if i > 16 {
-            result[0][i][1] = i2 - 16;
+            offsets[1] = i2 - 16;
         }
-        // black
+    }
+    // black
+    for (i, offsets) in result[1].iter_mut().enumerate() {
+        let i2 = i as u8;
         if i < 64 - 8 {
-            result[1][i][0] = i2 + 7;
-            result[1][i][1] = i2 + 7;
+            offsets[0] = i2 + 7;
+            offsets[1] = i2 + 7;
         } else {
-            result[1][i][0] = i2;
-            result[1][i][1] = i2;
+            offsets[0] = i2;
+            offsets[1] = i2;
         }
         if i < 64 - 16 {
-            result[1][i][0] = i2 + 16;
+            offsets[0] = i2 + 16;
         }
     }
     result
@@ -376,7 +549,7 @@ pub fn get_pawn_offsets() -> [[[u8; 2]; 64]; 2] {
 
 pub fn get_king_moves_masks() -> [u64; 64] {
     let mut moves: [u64; 64] = [0; 64];
-    for i in 0..64 {
+    for (i, square_moves) in moves.iter_mut().enumerate() {
         let mut king_moves = 0;
         let x: i32 = i as i32 % 8;
         let y: i32 = i as i32 / 8;
@@ -393,26 +566,49 @@ pub fn get_king_moves_masks() -> [u64; 64] {
             (1, 1),
         ] {
             if 0 <= x + xa && x + xa < 8 && 0 <= y + ya && y + ya < 8 {
-                king_moves |= 1 << (y + ya) * 8 + x + xa;
+                king_moves |= 1 << ((y + ya) * 8 + x + xa);
             }
         }
-        moves[i] = king_moves;
+        *square_moves = king_moves;
     }
     moves
 }
 
-pub fn generate_main_hashtables() -> MainHashtables {
-    let mut rook_mask_blockers_hashmaps: Vec<Vec<Option<u64>>> = vec![vec![None; 65536]; 64];
-    let rook_moves_masks_magical_numbers =
-        get_rook_moves_masks_magical_numbers(&mut rook_mask_blockers_hashmaps);
-    let mut bishop_mask_blockers_hashmaps: Vec<Vec<Option<u64>>> = vec![vec![None; 65536]; 64];
-    let bishop_moves_masks_magical_numbers =
-        get_bishop_moves_masks_magical_numbers(&mut bishop_mask_blockers_hashmaps);
+// the pext backend is only used when the pext feature is enabled and the cpu supports it, the
+// magic numbers are always computed as they also give the layout of slider_attacks
+pub fn generate_main_hashtables(magic_source: MagicSource) -> MainHashtables {
+    // picked first so slider_attacks is only filled once, in the order of the backend
+    let slider_backend = if cfg!(feature = "pext") && SliderBackend::Pext.is_available() {
+        SliderBackend::Pext
+    } else {
+        SliderBackend::Magic
+    };
+    let mut slider_attacks = Vec::new();
+    let (rook_moves_masks_magical_numbers, bishop_moves_masks_magical_numbers) = match magic_source
+    {
+        MagicSource::Precomputed => (ROOK_MAGIC_ENTRIES, BISHOP_MAGIC_ENTRIES),
+        MagicSource::Search(magic_seed) => {
+            let mut random_state = magic_seed;
+            (
+                get_rook_moves_masks_magical_numbers(&mut slider_attacks, &mut random_state),
+                get_bishop_moves_masks_magical_numbers(&mut slider_attacks, &mut random_state),
+            )
+        }
+    };
+    // the search fills the attacks in the magic order, they can be kept for the magic backend
+    if magic_source == MagicSource::Precomputed || slider_backend == SliderBackend::Pext {
+        slider_attacks = get_slider_attacks(
+            &rook_moves_masks_magical_numbers,
+            &bishop_moves_masks_magical_numbers,
+            slider_backend,
+        )
+        .expect("invalid precomputed magic numbers");
+    }
     MainHashtables {
-        rook_mask_blockers_hashmaps: rook_mask_blockers_hashmaps,
-        rook_moves_masks_magical_numbers: rook_moves_masks_magical_numbers,
-        bishop_mask_blockers_hashmaps: bishop_mask_blockers_hashmaps,
-        bishop_moves_masks_magical_numbers: bishop_moves_masks_magical_numbers,
+        slider_attacks,
+        slider_backend,
+        rook_moves_masks_magical_numbers,
+        bishop_moves_masks_magical_numbers,
         knight_move_masks: get_knight_moves_masks(),
         pawn_mask_takes_hashmaps: get_pawn_takes_hashmaps(),
         pawn_mask_blockers_hashmaps: get_pawn_mask_blockers_hashmaps(),
@@ -421,6 +617,34 @@ pub fn generate_main_hashtables() -> MainHashtables {
     }
 }
 
+// source of magic_constants.rs for the magic numbers of ma, written by the magic-gen subcommand
+pub fn get_magic_constants_source(ma: &MainHashtables) -> String {
+    let mut source = String::from(
+        "// generated by `pomme_chess magic-gen`, do not edit by hand\n\
+         use crate::binary_mask::MagicEntry;\n",
+    );
+    for (name, magic_entries) in [
+        ("ROOK_MAGIC_ENTRIES", &ma.rook_moves_masks_magical_numbers),
+        (
+            "BISHOP_MAGIC_ENTRIES",
+            &ma.bishop_moves_masks_magical_numbers,
+        ),
+    ] {
+        writeln!(source, "\npub const {}: [MagicEntry; 64] = [", name).unwrap();
+        for magic_entry in magic_entries {
+            writeln!(
+                source,
+                "    MagicEntry {{\n        mask: {:#018x},\n        magic_number: {:#018x},\n        \
+                 shift: {},\n        offset: {},\n    }},",
+                magic_entry.mask, magic_entry.magic_number, magic_entry.shift, magic_entry.offset
+            )
+            .unwrap();
+        }
+        source.push_str("];\n");
+    }
+    source
+}
+
 pub fn print_mask(mask: u64) {
     let x = format!("{mask:b}");
     let mut x = "0".repeat(64 - x.len()) + &x;
@@ -429,3 +653,78 @@ pub fn print_mask(mask: u64) {
     }
     println!("{}", x);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_numbers_are_reproducible() {
+        let get_magic_numbers = |magic_seed: u64| {
+            let ma = generate_main_hashtables(MagicSource::Search(magic_seed));
+            (
+                ma.rook_moves_masks_magical_numbers
+                    .map(|magic_entry| magic_entry.magic_number),
+                ma.bishop_moves_masks_magical_numbers
+                    .map(|magic_entry| magic_entry.magic_number),
+            )
+        };
+        // magic_constants.rs was generated from the default seed
+        assert_eq!(
+            get_magic_numbers(DEFAULT_MAGIC_SEED),
+            (
+                ROOK_MAGIC_ENTRIES.map(|magic_entry| magic_entry.magic_number),
+                BISHOP_MAGIC_ENTRIES.map(|magic_entry| magic_entry.magic_number),
+            )
+        );
+        assert_ne!(
+            get_magic_numbers(DEFAULT_MAGIC_SEED),
+            get_magic_numbers(DEFAULT_MAGIC_SEED + 1)
+        );
+    }
+
+    #[test]
+    fn precomputed_attacks_match_the_collisions() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let mut random_state = DEFAULT_MAGIC_SEED;
+        for _ in 0..1000 {
+            let board = next_random(&mut random_state) & next_random(&mut random_state);
+            for index in 0..64 {
+                assert_eq!(
+                    ma.get_rook_attacks(index, board),
+                    get_rook_moves_masks_collision(index, &board)
+                );
+                assert_eq!(
+                    ma.get_bishop_attacks(index, board),
+                    get_bishop_moves_masks_collision(index, &board)
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(
+        not(target_feature = "bmi2"),
+        ignore = "pext needs bmi2, build with -C target-feature=+bmi2"
+    )]
+    fn pext_backend_matches_magic_backend() {
+        let mut magic_ma = generate_main_hashtables(MagicSource::Precomputed);
+        magic_ma.set_slider_backend(SliderBackend::Magic);
+        let mut pext_ma = generate_main_hashtables(MagicSource::Precomputed);
+        pext_ma.set_slider_backend(SliderBackend::Pext);
+        let mut random_state = DEFAULT_MAGIC_SEED;
+        for _ in 0..1000 {
+            let board = next_random(&mut random_state) & next_random(&mut random_state);
+            for index in 0..64 {
+                assert_eq!(
+                    pext_ma.get_rook_attacks(index, board),
+                    magic_ma.get_rook_attacks(index, board)
+                );
+                assert_eq!(
+                    pext_ma.get_bishop_attacks(index, board),
+                    magic_ma.get_bishop_attacks(index, board)
+                );
+            }
+        }
+    }
+}
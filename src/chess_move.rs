@@ -0,0 +1,287 @@
+use std::fmt;
+
+use crate::notation::index_to_square;
+
+// move code
+// 2 first bits = type:
+//      00  -> normal move
+//      01  -> castling
+//      10  -> promotion
+//      11  -> en-passant
+// 2 next bits = depends on the type:
+//      castling    -> side, see CastlingSide
+//      promotion   -> piece, see PromotionPiece
+//      else        -> 00
+// 6 next bits = from_index
+// 6 last bits = to_index
+//
+// for castling from_index and to_index are the ones of the king
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveKind {
+    Normal = 0,
+    Castling = 1,
+    Promotion = 2,
+    EnPassant = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromotionPiece {
+    Queen = 0,
+    Rook = 1,
+    Bishop = 2,
+    Knight = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CastlingSide {
+    WhiteKingSide = 0,
+    WhiteQueenSide = 1,
+    BlackKingSide = 2,
+    BlackQueenSide = 3,
+}
+
+const MOVE_KINDS: [MoveKind; 4] = [
+    MoveKind::Normal,
+    MoveKind::Castling,
+    MoveKind::Promotion,
+    MoveKind::EnPassant,
+];
+
+pub const PROMOTION_PIECES: [PromotionPiece; 4] = [
+    PromotionPiece::Queen,
+    PromotionPiece::Rook,
+    PromotionPiece::Bishop,
+    PromotionPiece::Knight,
+];
+
+pub const CASTLING_SIDES: [CastlingSide; 4] = [
+    CastlingSide::WhiteKingSide,
+    CastlingSide::WhiteQueenSide,
+    CastlingSide::BlackKingSide,
+    CastlingSide::BlackQueenSide,
+];
+
+impl CastlingSide {
+    // king_from_index, king_to_index
+    pub fn get_king_indexes(self) -> (usize, usize) {
+        [(60, 62), (60, 58), (4, 6), (4, 2)][self as usize]
+    }
+}
+
+impl Move {
+    // the null move, a8a8, never generated by get_moves
+    pub const NULL: Move = Move(0);
+
+    pub fn new(from_index: usize, to_index: usize) -> Move {
+        Move(((from_index as u16) << 6) | to_index as u16)
+    }
+
+    pub fn new_promotion(from_index: usize, to_index: usize, piece: PromotionPiece) -> Move {
+        Move((0b10 << 14) | ((piece as u16) << 12) | Move::new(from_index, to_index).0)
+    }
+
+    pub fn new_en_passant(from_index: usize, to_index: usize) -> Move {
+        Move((0b11 << 14) | Move::new(from_index, to_index).0)
+    }
+
+    pub fn new_castling(side: CastlingSide) -> Move {
+        let (king_from_index, king_to_index) = side.get_king_indexes();
+        Move((0b01 << 14) | ((side as u16) << 12) | Move::new(king_from_index, king_to_index).0)
+    }
+
+    // the raw 16 bits, for compact storage
+    pub fn from_u16(move_code: u16) -> Move {
+        Move(move_code)
+    }
+
+    pub fn to_u16(self) -> u16 {
+        self.0
+    }
+
+    pub fn from_index(self) -> usize {
+        ((self.0 >> 6) & 0b111111) as usize
+    }
+
+    pub fn to_index(self) -> usize {
+        (self.0 & 0b111111) as usize
+    }
+
+    pub fn kind(self) -> MoveKind {
+        MOVE_KINDS[(self.0 >> 14) as usize]
+    }
+
+    pub fn promotion_piece(self) -> Option<PromotionPiece> {
+        match self.kind() {
+            MoveKind::Promotion => Some(PROMOTION_PIECES[((self.0 >> 12) & 0b11) as usize]),
+            _ => None,
+        }
+    }
+
+    pub fn castling_side(self) -> Option<CastlingSide> {
+        match self.kind() {
+            MoveKind::Castling => Some(CASTLING_SIDES[((self.0 >> 12) & 0b11) as usize]),
+            _ => None,
+        }
+    }
+}
+
+// uci notation: e2e4, e7e8q, e1g1
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            index_to_square(self.from_index()),
+            index_to_square(self.to_index())
+        )?;
+        if let Some(piece) = self.promotion_piece() {
+            write!(f, "{}", ['q', 'r', 'b', 'n'][piece as usize])?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChessBoard;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::{STARTING_FEN, parse_uci_move};
+
+    #[test]
+    fn normal_moves_are_encoded() {
+        // e2e4
+        let move_code = Move::new(52, 36);
+        assert_eq!(move_code.from_index(), 52);
+        assert_eq!(move_code.to_index(), 36);
+        assert_eq!(move_code.kind(), MoveKind::Normal);
+        assert_eq!(move_code.promotion_piece(), None);
+        assert_eq!(move_code.castling_side(), None);
+        assert_eq!(move_code.to_u16(), (52 << 6) | 36);
+        assert_eq!(Move::from_u16(move_code.to_u16()), move_code);
+
+        assert_eq!(Move::NULL, Move::default());
+        assert_eq!(Move::NULL.to_u16(), 0);
+    }
+
+    #[test]
+    fn promotions_are_encoded() {
+        for piece in PROMOTION_PIECES {
+            // e7e8
+            let move_code = Move::new_promotion(12, 4, piece);
+            assert_eq!(move_code.from_index(), 12);
+            assert_eq!(move_code.to_index(), 4);
+            assert_eq!(move_code.kind(), MoveKind::Promotion);
+            assert_eq!(move_code.promotion_piece(), Some(piece));
+            assert_eq!(move_code.castling_side(), None);
+            assert_eq!(move_code.to_u16() >> 14, MoveKind::Promotion as u16);
+            assert_eq!((move_code.to_u16() >> 12) & 0b11, piece as u16);
+            assert_eq!(Move::from_u16(move_code.to_u16()), move_code);
+        }
+    }
+
+    #[test]
+    fn castlings_are_encoded() {
+        for side in CASTLING_SIDES {
+            let move_code = Move::new_castling(side);
+            assert_eq!(
+                (move_code.from_index(), move_code.to_index()),
+                side.get_king_indexes()
+            );
+            assert_eq!(move_code.kind(), MoveKind::Castling);
+            assert_eq!(move_code.castling_side(), Some(side));
+            assert_eq!(move_code.promotion_piece(), None);
+            assert_eq!(move_code.to_u16() >> 14, MoveKind::Castling as u16);
+            assert_eq!((move_code.to_u16() >> 12) & 0b11, side as u16);
+            assert_eq!(Move::from_u16(move_code.to_u16()), move_code);
+        }
+    }
+
+    #[test]
+    fn en_passants_are_encoded() {
+        // e5d6
+        let move_code = Move::new_en_passant(28, 19);
+        assert_eq!(move_code.from_index(), 28);
+        assert_eq!(move_code.to_index(), 19);
+        assert_eq!(move_code.kind(), MoveKind::EnPassant);
+        assert_eq!(move_code.promotion_piece(), None);
+        assert_eq!(move_code.castling_side(), None);
+        assert_eq!(move_code.to_u16() >> 14, MoveKind::EnPassant as u16);
+        assert_eq!((move_code.to_u16() >> 12) & 0b11, 0);
+        assert_eq!(Move::from_u16(move_code.to_u16()), move_code);
+    }
+
+    #[test]
+    fn uci_notation_round_trips() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let cases = [
+            (STARTING_FEN, Move::new(52, 36), "e2e4"),
+            (
+                "8/4P2k/8/8/8/8/8/4K3 w - - 0 1",
+                Move::new_promotion(12, 4, PromotionPiece::Queen),
+                "e7e8q",
+            ),
+            (
+                "8/4P2k/8/8/8/8/8/4K3 w - - 0 1",
+                Move::new_promotion(12, 4, PromotionPiece::Knight),
+                "e7e8n",
+            ),
+            (
+                "4k3/8/8/8/8/8/p7/4K3 b - - 0 1",
+                Move::new_promotion(48, 56, PromotionPiece::Rook),
+                "a2a1r",
+            ),
+            (
+                "4k3/8/8/8/8/8/p7/4K3 b - - 0 1",
+                Move::new_promotion(48, 56, PromotionPiece::Bishop),
+                "a2a1b",
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+                Move::new_en_passant(28, 19),
+                "e5d6",
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                Move::new_castling(CastlingSide::WhiteKingSide),
+                "e1g1",
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                Move::new_castling(CastlingSide::WhiteQueenSide),
+                "e1c1",
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                Move::new_castling(CastlingSide::BlackKingSide),
+                "e8g8",
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                Move::new_castling(CastlingSide::BlackQueenSide),
+                "e8c8",
+            ),
+        ];
+        for (fen, move_code, notation) in cases {
+            assert_eq!(move_code.to_string(), notation);
+            assert_eq!(format!("{:?}", move_code), notation);
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(
+                parse_uci_move(&chessboard, &ma, notation),
+                Ok(move_code),
+                "{} in {}",
+                notation,
+                fen
+            );
+        }
+    }
+}
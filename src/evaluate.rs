@@ -0,0 +1,326 @@
+use crate::binary_mask::MainHashtables;
+use crate::{ChessBoard, TypePiece};
+
+// simple values by TypePiece as usize % 6, the king isn't counted
+// used where a rough idea of the value of a piece is enough (move ordering, exchanges)
+pub const PIECE_VALUES: [i32; 6] = [0, 900, 500, 330, 320, 100];
+
+// tapered evaluation, middlegame and endgame scores are interpolated according to the phase
+// values and piece-square tables from PeSTO, by TypePiece as usize % 6
+const MIDDLEGAME_VALUES: [i32; 6] = [0, 1025, 477, 365, 337, 82];
+const ENDGAME_VALUES: [i32; 6] = [0, 936, 512, 297, 281, 94];
+// weight of each piece in the phase, 24 = middlegame with all the pieces, 0 = pawn endgame
+const PHASE_WEIGHTS: [i32; 6] = [0, 4, 2, 1, 1, 0];
+const MAX_PHASE: i32 = 24;
+
+const BISHOP_PAIR: (i32, i32) = (30, 50);
+// (middlegame, endgame) by attacked square, the average number of squares is subtracted first
+const MOBILITY_WEIGHTS: [(i32, i32); 6] = [(0, 0), (1, 2), (2, 4), (5, 5), (4, 4), (0, 0)];
+const MOBILITY_AVERAGES: [i32; 6] = [0, 14, 7, 7, 4, 0];
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-10, -15);
+// by number of ranks the pawn has advanced from its starting rank
+const PASSED_PAWN: [(i32, i32); 6] = [(5, 10), (10, 20), (20, 40), (35, 70), (60, 120), (100, 200)];
+
+const FILE_A: u64 = 0x0101010101010101;
+
+// indexed as the board (a8 = 0) for white, black uses index ^ 56
+#[rustfmt::skip]
+const MIDDLEGAME_TABLES: [[i32; 64]; 6] = [
+    // king
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+    // queen
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    // rook
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    // bishop
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    // knight
+    [
+        -167, -89, -34, -49,  61, -97, -15, -107,
+         -73, -41,  72,  36,  23,  62,   7,  -17,
+         -47,  60,  37,  65,  84, 129,  73,   44,
+          -9,  17,  19,  53,  37,  69,  18,   22,
+         -13,   4,  16,  13,  28,  19,  21,   -8,
+         -23,  -9,  12,  10,  19,  17,  25,  -16,
+         -29, -53, -12,  -3,  -1,  18, -14,  -19,
+        -105, -21, -58, -33, -17, -28, -19,  -23,
+    ],
+    // pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+];
+
+#[rustfmt::skip]
+const ENDGAME_TABLES: [[i32; 64]; 6] = [
+    // king
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+    // queen
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    // rook
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    // bishop
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    // knight
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    // pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+];
+
+// (middlegame, endgame) score of one side, color as in players (1 = white)
+fn evaluate_side(chessboard: &ChessBoard, ma: &MainHashtables, color: usize) -> (i32, i32) {
+    let offset = if color == 1 { 0 } else { 6 };
+    // black reads the tables upside down
+    let flip = if color == 1 { 0 } else { 56 };
+    let player = chessboard.players[color];
+    let mut middlegame = 0;
+    let mut endgame = 0;
+
+    for piece in 0..6 {
+        let mut pieces = chessboard.pieces[piece + offset];
+        while pieces != 0 {
+            let index = pieces.trailing_zeros() as usize;
+            middlegame += MIDDLEGAME_VALUES[piece] + MIDDLEGAME_TABLES[piece][index ^ flip];
+            endgame += ENDGAME_VALUES[piece] + ENDGAME_TABLES[piece][index ^ flip];
+
+            // mobility
+            let attacks = match piece {
+                1 => {
+                    ma.get_rook_attacks(index, chessboard.board)
+                        | ma.get_bishop_attacks(index, chessboard.board)
+                }
+                2 => ma.get_rook_attacks(index, chessboard.board),
+                3 => ma.get_bishop_attacks(index, chessboard.board),
+                4 => ma.knight_move_masks[index],
+                _ => 0,
+            };
+            let mobility = (attacks & !player).count_ones() as i32 - MOBILITY_AVERAGES[piece];
+            middlegame += mobility * MOBILITY_WEIGHTS[piece].0;
+            endgame += mobility * MOBILITY_WEIGHTS[piece].1;
+
+            pieces ^= 1 << index;
+        }
+    }
+
+    if chessboard.pieces[TypePiece::WhiteBishop as usize + offset].count_ones() >= 2 {
+        middlegame += BISHOP_PAIR.0;
+        endgame += BISHOP_PAIR.1;
+    }
+
+    // pawn structure
+    let pawns = chessboard.pieces[TypePiece::WhitePawn as usize + offset];
+    let opponent_pawns = chessboard.pieces[TypePiece::BlackPawn as usize - offset];
+    for x in 0..8 {
+        let file = FILE_A << x;
+        let pawns_on_file = (pawns & file).count_ones() as i32;
+        if pawns_on_file == 0 {
+            continue;
+        }
+        let adjacent_files = ((file << 1) & !FILE_A) | ((file >> 1) & !(FILE_A << 7));
+        middlegame += (pawns_on_file - 1) * DOUBLED_PAWN.0;
+        endgame += (pawns_on_file - 1) * DOUBLED_PAWN.1;
+        if pawns & adjacent_files == 0 {
+            middlegame += pawns_on_file * ISOLATED_PAWN.0;
+            endgame += pawns_on_file * ISOLATED_PAWN.1;
+        }
+    }
+    let mut remaining_pawns = pawns;
+    while remaining_pawns != 0 {
+        let index = remaining_pawns.trailing_zeros() as usize;
+        let x = index % 8;
+        let y = index / 8;
+        let files =
+            (FILE_A << x) | ((FILE_A << x) << 1 & !FILE_A) | ((FILE_A << x) >> 1 & !(FILE_A << 7));
+        // squares in front of the pawn, towards the rank 8 for white
+        let (front, advanced_ranks) = if color == 1 {
+            ((1u64 << (y * 8)) - 1, 6 - y)
+        } else {
+            (!((1u64 << ((y + 1) * 8)) - 1), y - 1)
+        };
+        if opponent_pawns & files & front == 0 {
+            middlegame += PASSED_PAWN[advanced_ranks].0;
+            endgame += PASSED_PAWN[advanced_ranks].1;
+        }
+        remaining_pawns ^= 1 << index;
+    }
+
+    (middlegame, endgame)
+}
+
+// score of the position in centipawns from the point of view of the player to play
+pub fn evaluate(chessboard: &ChessBoard, ma: &MainHashtables) -> i32 {
+    let (white_middlegame, white_endgame) = evaluate_side(chessboard, ma, 1);
+    let (black_middlegame, black_endgame) = evaluate_side(chessboard, ma, 0);
+
+    let mut phase = 0;
+    for (piece, weight) in PHASE_WEIGHTS.iter().enumerate() {
+        phase +=
+            weight * (chessboard.pieces[piece] | chessboard.pieces[piece + 6]).count_ones() as i32;
+    }
+    let phase = phase.min(MAX_PHASE);
+
+    let middlegame = white_middlegame - black_middlegame;
+    let endgame = white_endgame - black_endgame;
+    let score = (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE;
+    if chessboard.is_white_to_play {
+        score
+    } else {
+        -score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::STARTING_FEN;
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        assert_eq!(
+            evaluate(&ChessBoard::from_fen(STARTING_FEN).unwrap(), &ma),
+            0
+        );
+        // same positions with colors swapped and the board mirrored
+        let mirrored_positions = [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1",
+            ),
+            (
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                "8/4p1p1/8/1r3P1K/kp5R/3P4/2P5/8 b - - 0 1",
+            ),
+        ];
+        for (fen, mirrored_fen) in mirrored_positions {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            let mirrored = ChessBoard::from_fen(mirrored_fen).unwrap();
+            assert_eq!(evaluate(&chessboard, &ma), evaluate(&mirrored, &ma));
+        }
+    }
+
+    #[test]
+    fn passed_pawn_is_worth_more_when_advanced() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        // a lone pawn for the player to play, only the kings are left so the score is the endgame
+        // one, the square table is taken out as it doesn't always grow with the rank
+        for (pawn, player_turn) in [("4P3", "w"), ("3p4", "b")] {
+            let mut previous_score = i32::MIN;
+            for advanced_ranks in 0..6 {
+                let mut ranks = ["8"; 8];
+                ranks[0] = "k7";
+                ranks[7] = "7K";
+                let (y, x, flip) = if player_turn == "w" {
+                    (6 - advanced_ranks, 4, 0)
+                } else {
+                    (1 + advanced_ranks, 3, 56)
+                };
+                ranks[y] = pawn;
+                let fen = format!("{} {} - - 0 1", ranks.join("/"), player_turn);
+                let score = evaluate(&ChessBoard::from_fen(&fen).unwrap(), &ma)
+                    - ENDGAME_TABLES[5][(y * 8 + x) ^ flip];
+                assert!(score > previous_score, "{}", fen);
+                previous_score = score;
+            }
+        }
+    }
+}
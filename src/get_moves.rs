@@ -1,15 +1,15 @@
-use crate::ChessBoard;
-use crate::binary_mask;
 use crate::binary_mask::MainHashtables;
+use crate::chess_move::{CastlingSide, Move, MoveKind, PROMOTION_PIECES};
+use crate::{ChessBoard, TypePiece};
 
 pub struct MovesStruct {
     // the max number of possible moves in a obtainable chess position is 218
-    pub moves: [u16; 255],
+    pub moves: [Move; 255],
     pub move_number: u8,
 }
 
 impl MovesStruct {
-    fn push(&mut self, move_code: u16) {
+    fn push(&mut self, move_code: Move) {
         assert!(
             self.move_number <= 254,
             "a position with more than 255 moves shouldn't be possible"
@@ -26,19 +26,36 @@ impl MovesStruct {
 
     pub fn init() -> MovesStruct {
         MovesStruct {
-            moves: [0u16; 255],
+            moves: [Move::NULL; 255],
             move_number: 0,
         }
     }
 }
 
-const MOVE_FUNC_BY_PIECE: [for<'a, 'b> fn(
-    &'a ChessBoard,
-    u8,
-    &'b MainHashtables,
-    u64,
-    &mut MovesStruct,
-); 12] = [
+// the moves generated by get_moves_by_type
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GenType {
+    All,
+    // captures (en-passant included) and promotions, for the quiescence search
+    Captures,
+    // every move that isn't generated by Captures
+    Quiets,
+}
+
+// the squares a pawn promotes on
+const PROMOTION_SQUARES: u64 = 0xff000000000000ff;
+
+// by color
+const KING_SIDE_CASTLINGS: [CastlingSide; 2] =
+    [CastlingSide::BlackKingSide, CastlingSide::WhiteKingSide];
+const QUEEN_SIDE_CASTLINGS: [CastlingSide; 2] =
+    [CastlingSide::BlackQueenSide, CastlingSide::WhiteQueenSide];
+
+type MoveFunc = for<'a, 'b> fn(&'a ChessBoard, u8, &'b MainHashtables, u64, &mut MovesStruct);
+
+// the u64 given to each function is the mask of the squares the piece is allowed to move to
+// (not occupied by the player, resolving the check and staying on the pin ray)
+const MOVE_FUNC_BY_PIECE: [MoveFunc; 12] = [
     ChessBoard::get_king_moves,
     ChessBoard::get_queen_moves,
     ChessBoard::get_rook_moves,
@@ -53,33 +70,23 @@ const MOVE_FUNC_BY_PIECE: [for<'a, 'b> fn(
     ChessBoard::get_pawn_moves,
 ];
 
-// u16: last 6 bits = to_index, 6 bits before that = from_index
-fn move_mask_to_u16(from_index: u8, mut mask: u64, moves: &mut MovesStruct) {
-    let from_index = from_index as u16;
-    let from_index = from_index << 6;
-
+fn move_mask_to_moves(from_index: u8, mut mask: u64, moves: &mut MovesStruct) {
     while mask != 0 {
-        let to_index = mask.trailing_zeros() as u16;
-        let move_u16 = to_index | from_index;
-        moves.push(move_u16);
+        let to_index = mask.trailing_zeros() as usize;
+        moves.push(Move::new(from_index as usize, to_index));
         mask ^= 1 << to_index;
     }
 }
 
-fn pawn_move_mask_to_u16(from_index: u8, mut mask: u64, moves: &mut MovesStruct) {
-    let from_index = from_index as u16;
-    let from_index = from_index << 6;
-
+fn pawn_move_mask_to_moves(from_index: u8, mut mask: u64, moves: &mut MovesStruct) {
     while mask != 0 {
-        let to_index = mask.trailing_zeros() as u16;
-        let move_u16 = to_index | from_index;
-        if to_index < 8 || to_index > 55 {
-            moves.push(move_u16 | 0b1000000000000000);
-            moves.push(move_u16 | 0b1001000000000000);
-            moves.push(move_u16 | 0b1010000000000000);
-            moves.push(move_u16 | 0b1011000000000000);
+        let to_index = mask.trailing_zeros() as usize;
+        if !(8..=55).contains(&to_index) {
+            for piece in PROMOTION_PIECES {
+                moves.push(Move::new_promotion(from_index as usize, to_index, piece));
+            }
         } else {
-            moves.push(move_u16);
+            moves.push(Move::new(from_index as usize, to_index));
         }
         mask ^= 1 << to_index;
     }
@@ -89,81 +96,116 @@ impl ChessBoard {
     fn get_rook_moves(
         &self,
         index: u8,
-        ma: &binary_mask::MainHashtables,
-        player: u64,
+        ma: &MainHashtables,
+        targets: u64,
         moves: &mut MovesStruct,
     ) {
-        let square_mask = &ma.rook_moves_masks_magical_numbers[index as usize].mask;
-        let magical_number = &ma.rook_moves_masks_magical_numbers[index as usize].magic_number;
-        let hashkey = (self.board & square_mask).wrapping_mul(*magical_number) >> 48;
-        let rook_moves = ma.rook_mask_blockers_hashmaps[index as usize][hashkey as usize].unwrap();
-        move_mask_to_u16(index, rook_moves & player ^ rook_moves, moves);
+        let rook_moves = ma.get_rook_attacks(index as usize, self.board);
+        move_mask_to_moves(index, rook_moves & targets, moves);
     }
 
     fn get_bishop_moves(
         &self,
         index: u8,
-        ma: &binary_mask::MainHashtables,
-        player: u64,
+        ma: &MainHashtables,
+        targets: u64,
         moves: &mut MovesStruct,
     ) {
-        let square_mask = &ma.bishop_moves_masks_magical_numbers[index as usize].mask;
-        let magical_number = &ma.bishop_moves_masks_magical_numbers[index as usize].magic_number;
-        let hashkey = (self.board & square_mask).wrapping_mul(*magical_number) >> 48;
-        let bishop_moves =
-            ma.bishop_mask_blockers_hashmaps[index as usize][hashkey as usize].unwrap();
-        move_mask_to_u16(index, bishop_moves & player ^ bishop_moves, moves);
+        let bishop_moves = ma.get_bishop_attacks(index as usize, self.board);
+        move_mask_to_moves(index, bishop_moves & targets, moves);
     }
 
     fn get_knight_moves(
         &self,
         index: u8,
-        ma: &binary_mask::MainHashtables,
-        player: u64,
+        ma: &MainHashtables,
+        targets: u64,
         moves: &mut MovesStruct,
     ) {
         let knight_moves = ma.knight_move_masks[index as usize];
-        move_mask_to_u16(index, knight_moves & player ^ knight_moves, moves);
+        move_mask_to_moves(index, knight_moves & targets, moves);
     }
 
     fn get_queen_moves(
         &self,
         index: u8,
-        ma: &binary_mask::MainHashtables,
-        player: u64,
+        ma: &MainHashtables,
+        targets: u64,
         moves: &mut MovesStruct,
     ) {
         // TODO optimize specifically for the queen
-        self.get_bishop_moves(index, ma, player, moves);
-        self.get_rook_moves(index, ma, player, moves);
+        self.get_bishop_moves(index, ma, targets, moves);
+        self.get_rook_moves(index, ma, targets, moves);
     }
 
     fn get_pawn_moves(
         &self,
         index: u8,
-        ma: &binary_mask::MainHashtables,
-        player: u64,
+        ma: &MainHashtables,
+        targets: u64,
         moves: &mut MovesStruct,
     ) {
         let index = index as usize;
         let color = !self.is_white_to_play as usize;
-        let pawn_takes =
-            ma.pawn_mask_takes_hashmaps[color][index] & ((self.board ^ player) | self.en_passant);
+        let opponent = self.players[color];
+        let pawn_takes = ma.pawn_mask_takes_hashmaps[color][index] & opponent;
         let pawn_blockers = ma.pawn_mask_blockers_hashmaps[color][index][0] & (self.board);
         let hashkey = (pawn_blockers >> ma.pawn_offsets[color][index][0])
             | (pawn_blockers >> ma.pawn_offsets[color][index][1]) & 0b11;
         let pawn_moves = ma.pawn_mask_blockers_hashmaps[color][index][hashkey as usize];
-        let move_code = pawn_moves | pawn_takes;
-        pawn_move_mask_to_u16(index as u8, move_code, moves);
+        let move_code = (pawn_moves | pawn_takes) & targets;
+        pawn_move_mask_to_moves(index as u8, move_code, moves);
+    }
+
+    // the taken pawn isn't on the destination square so the legality is checked by playing
+    // the move on the occupancy instead of relying on the targets of get_moves
+    fn get_en_passant_moves(&self, ma: &MainHashtables, moves: &mut MovesStruct) {
+        if self.en_passant == 0 {
+            return;
+        }
+        let color = !self.is_white_to_play as usize;
+        let to_index = self.en_passant.trailing_zeros() as usize;
+        // a player pawn can take if it is where an opponent pawn on the en-passant square would take
+        let mut pawns = ma.pawn_mask_takes_hashmaps[1 - color][to_index]
+            & self.pieces[TypePiece::WhitePawn as usize + color * 6];
+        let taken_pawn = if self.is_white_to_play {
+            self.en_passant << 8
+        } else {
+            self.en_passant >> 8
+        };
+        let king = self.pieces[TypePiece::WhiteKing as usize + color * 6];
+        let king_index = king.trailing_zeros() as u8;
+        while pawns != 0 {
+            let index = pawns.trailing_zeros() as usize;
+            let board_after = self.board ^ (1 << index) ^ taken_pawn | self.en_passant;
+            if self.get_attackers(ma, king_index, color, board_after) & !taken_pawn == 0 {
+                moves.push(Move::new_en_passant(index, to_index));
+            }
+            pawns ^= 1 << index;
+        }
     }
 
     fn get_king_moves(
         &self,
         index: u8,
-        ma: &binary_mask::MainHashtables,
-        player: u64,
+        ma: &MainHashtables,
+        targets: u64,
         moves: &mut MovesStruct,
     ) {
+        let other_color = !self.is_white_to_play as usize;
+        // the king is removed from the board so it can't hide behind itself from a slider
+        let board_without_king = self.board ^ (1 << index);
+        let mut king_moves = ma.king_move_masks[index as usize] & targets;
+        while king_moves != 0 {
+            let to_index = king_moves.trailing_zeros() as u8;
+            if self.get_attackers(ma, to_index, other_color, board_without_king) == 0 {
+                moves.push(Move::new(index as usize, to_index as usize));
+            }
+            king_moves ^= 1 << to_index;
+        }
+    }
+
+    fn get_castling_moves(&self, index: u8, ma: &MainHashtables, moves: &mut MovesStruct) {
         let king_side_mask = [
             0b1100000,
             0b110000000000000000000000000000000000000000000000000000000000000,
@@ -173,33 +215,299 @@ impl ChessBoard {
             0b111000000000000000000000000000000000000000000000000000000000,
         ];
         let color = self.is_white_to_play as usize;
-        let other_color = !self.is_white_to_play as u16;
-        let king_moves = ma.king_move_masks[index as usize];
-        move_mask_to_u16(index as u8, king_moves & player ^ king_moves, moves);
-        if self.king_side_castle[color] && self.board & king_side_mask[color] == 0 {
-            moves.push((01 << 14) | (other_color << 13));
+
+        // the king can't be in check nor go through or onto an attacked square
+        let is_attacked = |square: u8| self.is_square_attacked(ma, square, !self.is_white_to_play);
+        if !self.king_side_castle[color] && !self.queen_side_castle[color] || is_attacked(index) {
+            return;
+        }
+        if self.king_side_castle[color]
+            && self.board & king_side_mask[color] == 0
+            && !is_attacked(index + 1)
+            && !is_attacked(index + 2)
+        {
+            moves.push(Move::new_castling(KING_SIDE_CASTLINGS[color]));
+        }
+        if self.queen_side_castle[color]
+            && self.board & queen_side_mask[color] == 0
+            && !is_attacked(index - 1)
+            && !is_attacked(index - 2)
+        {
+            moves.push(Move::new_castling(QUEEN_SIDE_CASTLINGS[color]));
+        }
+    }
+
+    // whether get_moves would give move_code, for the moves coming from another position (tt move,
+    // killers) without generating every move
+    pub fn is_legal_move(&self, ma: &MainHashtables, move_code: Move) -> bool {
+        if move_code == Move::NULL {
+            return false;
         }
-        if self.queen_side_castle[color] && self.board & queen_side_mask[color] == 0 {
-            moves.push((01 << 14) | (other_color << 13) | (1 << 12));
+        let color = self.is_white_to_play as usize;
+        let other_color = !self.is_white_to_play as usize;
+        let king = self.pieces[TypePiece::WhiteKing as usize + other_color * 6];
+        let king_index = king.trailing_zeros() as u8;
+        let from_index = move_code.from_index();
+        let to_mask = 1 << move_code.to_index();
+        let mut moves = MovesStruct::init();
+        match move_code.kind() {
+            MoveKind::Castling => self.get_castling_moves(king_index, ma, &mut moves),
+            MoveKind::EnPassant => self.get_en_passant_moves(ma, &mut moves),
+            MoveKind::Normal | MoveKind::Promotion => {
+                if self.players[color] & (1 << from_index) == 0 {
+                    return false;
+                }
+                // the moves of the king are only generated on safe squares, the other pieces
+                // mustn't leave the king in check once the move is played on the occupancy
+                if king & (1 << from_index) == 0 {
+                    let board_after = self.board ^ (1 << from_index) | to_mask;
+                    if self.get_attackers(ma, king_index, other_color, board_after) & !to_mask != 0
+                    {
+                        return false;
+                    }
+                }
+                let piece = self.pieces_by_index[from_index];
+                let targets = to_mask & !self.players[color];
+                MOVE_FUNC_BY_PIECE[piece as usize](self, from_index as u8, ma, targets, &mut moves);
+            }
         }
+        moves.moves[..moves.move_number as usize].contains(&move_code)
+    }
+
+    pub fn get_moves(&self, ma: &MainHashtables, moves: &mut MovesStruct) {
+        self.get_moves_by_type(ma, GenType::All, moves);
     }
 
-    pub fn get_moves(&self, ma: &binary_mask::MainHashtables, moves: &mut MovesStruct) {
+    pub fn get_moves_by_type(
+        &self,
+        ma: &MainHashtables,
+        gen_type: GenType,
+        moves: &mut MovesStruct,
+    ) {
         let color = self.is_white_to_play as usize;
-        let mut player_pieces = self.players[color];
+        let other_color = !self.is_white_to_play as usize;
+        let king = self.pieces[TypePiece::WhiteKing as usize + other_color * 6];
+        let king_index = king.trailing_zeros() as u8;
         moves.reset();
 
+        // pushes onto the last rank are promotions so they count as captures
+        let (gen_mask, pawn_gen_mask) = match gen_type {
+            GenType::All => (!self.players[color], !self.players[color]),
+            GenType::Captures => (
+                self.players[other_color],
+                self.players[other_color] | PROMOTION_SQUARES & !self.board,
+            ),
+            GenType::Quiets => (!self.board, !self.board & !PROMOTION_SQUARES),
+        };
+
+        self.get_king_moves(king_index, ma, gen_mask, moves);
+        if gen_type != GenType::Captures {
+            self.get_castling_moves(king_index, ma, moves);
+        }
+
+        // checks
+        let checkers = self.get_attackers(ma, king_index, other_color, self.board);
+        if checkers.count_ones() > 1 {
+            // double check, only the king can move
+            return;
+        }
+        let check_mask = if checkers == 0 {
+            u64::MAX
+        } else {
+            checkers | ma.get_between_mask(king_index as usize, checkers.trailing_zeros() as usize)
+        };
+
+        // pins
+        // a sniper is an opponent slider that would attack the king if no player piece were on
+        // its way, if exactly one player piece is between them that piece is pinned
+        let offset = other_color * 6;
+        let opponent = self.players[other_color];
+        let queens = self.pieces[TypePiece::BlackQueen as usize - offset];
+        let rooks = self.pieces[TypePiece::BlackRook as usize - offset];
+        let bishops = self.pieces[TypePiece::BlackBishop as usize - offset];
+        let mut snipers = (ma.get_rook_attacks(king_index as usize, opponent) & (queens | rooks))
+            | (ma.get_bishop_attacks(king_index as usize, opponent) & (queens | bishops));
+        let mut pinned = 0;
+        let mut pin_rays = [0u64; 8];
+        let mut pin_rays_number = 0;
+        while snipers != 0 {
+            let sniper_index = snipers.trailing_zeros() as usize;
+            let between = ma.get_between_mask(king_index as usize, sniper_index);
+            let blockers = between & self.board;
+            if blockers.count_ones() == 1 && blockers & self.players[color] != 0 {
+                pinned |= blockers;
+                pin_rays[pin_rays_number] = between | (1 << sniper_index);
+                pin_rays_number += 1;
+            }
+            snipers ^= 1 << sniper_index;
+        }
+
+        let mut player_pieces = self.players[color] ^ king;
         while player_pieces != 0 {
             let i = player_pieces.trailing_zeros() as usize;
             let index = 1 << i;
-            let _ = &mut MOVE_FUNC_BY_PIECE[self.pieces_by_index[i] as usize](
-                self,
-                i as u8,
-                &ma,
-                self.players[color],
-                moves,
-            );
+            let piece = self.pieces_by_index[i];
+            let mut targets = check_mask
+                & if piece as usize % 6 == TypePiece::WhitePawn as usize {
+                    pawn_gen_mask
+                } else {
+                    gen_mask
+                };
+            if pinned & index != 0 {
+                targets &= pin_rays[..pin_rays_number]
+                    .iter()
+                    .find(|pin_ray| *pin_ray & index != 0)
+                    .unwrap();
+            }
+            MOVE_FUNC_BY_PIECE[piece as usize](self, i as u8, ma, targets, moves);
             player_pieces ^= index;
         }
+        if gen_type != GenType::Quiets {
+            self.get_en_passant_moves(ma, moves);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::chess_move::MoveKind;
+
+    // mask of the squares the piece on from_index can move to
+    fn get_destinations(chessboard: &ChessBoard, ma: &MainHashtables, from_index: usize) -> u64 {
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves(ma, &mut moves);
+        moves.moves[..moves.move_number as usize]
+            .iter()
+            .filter(|move_code| move_code.from_index() == from_index)
+            .fold(0, |mask, move_code| mask | 1 << move_code.to_index())
+    }
+
+    #[test]
+    fn pinned_pieces_stay_on_the_pin_ray() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        // rook e4 pinned by the rook e8, knight d2 pinned by the bishop b4
+        let chessboard = ChessBoard::from_fen("4r2k/8/8/8/1b2R3/8/3N4/4K3 w - - 0 1").unwrap();
+        let e_file = (1 << 4) | (1 << 12) | (1 << 20) | (1 << 28) | (1 << 44) | (1 << 52);
+        assert_eq!(get_destinations(&chessboard, &ma, 36), e_file);
+        assert_eq!(get_destinations(&chessboard, &ma, 51), 0);
+    }
+
+    #[test]
+    fn checks_are_resolved() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        // the rook a3 can only block on e3
+        let chessboard = ChessBoard::from_fen("4r2k/8/8/8/8/R7/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(get_destinations(&chessboard, &ma, 40), 1 << 44);
+        // double check by the rook e8 and the bishop b4, only the king moves and not along the
+        // checking lines
+        let chessboard = ChessBoard::from_fen("4r2k/8/8/8/1b6/R7/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(get_destinations(&chessboard, &ma, 40), 0);
+        assert_eq!(
+            get_destinations(&chessboard, &ma, 60),
+            (1 << 53) | (1 << 59) | (1 << 61)
+        );
+    }
+
+    #[test]
+    fn en_passant_exposing_the_king_is_illegal() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        // d7d5 was just played, exd6 would leave the king a5 in check from the rook h5
+        let chessboard = ChessBoard::from_fen("7k/8/8/K2pP3/8/8/8/8 w - d6 0 1").unwrap();
+        assert_eq!(
+            get_destinations(&chessboard, &ma, 28),
+            (1 << 19) | (1 << 20)
+        );
+        let chessboard = ChessBoard::from_fen("7k/8/8/K2pP2r/8/8/8/8 w - d6 0 1").unwrap();
+        assert_eq!(get_destinations(&chessboard, &ma, 28), 1 << 20);
+    }
+
+    fn get_sorted_moves(
+        chessboard: &ChessBoard,
+        ma: &MainHashtables,
+        gen_type: GenType,
+    ) -> Vec<Move> {
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves_by_type(ma, gen_type, &mut moves);
+        let mut moves = moves.moves[..moves.move_number as usize].to_vec();
+        moves.sort_by_key(|move_code| move_code.to_u16());
+        moves
+    }
+
+    fn assert_moves_are_filtered(chessboard: &mut ChessBoard, ma: &MainHashtables, depth: u8) {
+        let opponent = chessboard.players[!chessboard.is_white_to_play as usize];
+        let (expected_captures, expected_quiets): (Vec<Move>, Vec<Move>) =
+            get_sorted_moves(chessboard, ma, GenType::All)
+                .into_iter()
+                .partition(|move_code| {
+                    opponent & (1 << move_code.to_index()) != 0
+                        || matches!(move_code.kind(), MoveKind::EnPassant | MoveKind::Promotion)
+                });
+        let fen = chessboard.get_fen();
+        assert_eq!(
+            get_sorted_moves(chessboard, ma, GenType::Captures),
+            expected_captures,
+            "{}",
+            fen
+        );
+        assert_eq!(
+            get_sorted_moves(chessboard, ma, GenType::Quiets),
+            expected_quiets,
+            "{}",
+            fen
+        );
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves(ma, &mut moves);
+
+        if depth == 0 {
+            return;
+        }
+        for move_code in &moves.moves[..moves.move_number as usize] {
+            chessboard.make_move(*move_code);
+            assert_moves_are_filtered(chessboard, ma, depth - 1);
+            chessboard.unmake_move();
+        }
+    }
+
+    #[test]
+    fn captures_and_quiets_generation() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_moves_are_filtered(&mut chessboard, &ma, 2);
+        }
+    }
+
+    #[test]
+    fn legal_moves_are_recognized() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "4r2k/8/8/8/1b2R3/8/3N4/4K3 w - - 0 1",
+            "4r2k/8/8/8/1b6/R7/8/4K3 w - - 0 1",
+            "7k/8/8/K2pP2r/8/8/8/8 w - d6 0 1",
+            "7k/8/8/K2pP3/8/8/8/8 w - d6 0 1",
+        ] {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            let mut moves = MovesStruct::init();
+            chessboard.get_moves(&ma, &mut moves);
+            let legal_moves = &moves.moves[..moves.move_number as usize];
+            for move_code in (0..=u16::MAX).map(Move::from_u16) {
+                assert_eq!(
+                    chessboard.is_legal_move(&ma, move_code),
+                    legal_moves.contains(&move_code),
+                    "{} {}",
+                    fen,
+                    move_code
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,530 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::binary_mask::{
+    MagicEntry, MainHashtables, SliderBackend, get_bishop_moves_masks, get_king_moves_masks,
+    get_knight_moves_masks, get_pawn_mask_blockers_hashmaps, get_pawn_offsets,
+    get_pawn_takes_hashmaps, get_rook_moves_masks, get_slider_attacks,
+};
+
+// layout of the file, every number is little-endian:
+// FILE_SIGNATURE, version u32, slider backend u8,
+// rook then bishop magic entries (mask u64, magic number u64, shift u8, offset u32) * 64,
+// number of slider attacks u64, slider attacks u64 * n,
+// knight moves u64 * 64, pawn takes u64 * 2 * 64, pawn blockers u64 * 2 * 64 * 4,
+// pawn offsets u8 * 2 * 64 * 2, king moves u64 * 64,
+// checksum u64 (fnv-1a of everything before it)
+const FILE_SIGNATURE: &[u8; 8] = b"POMMEMH\0";
+// to increment whenever the layout or the content of the tables changes
+pub const FILE_VERSION: u32 = 1;
+const HEADER_SIZE: usize = FILE_SIGNATURE.len() + 4;
+
+#[derive(Debug)]
+pub enum HashtablesFileError {
+    Io(io::Error),
+    NotAHashtablesFile,
+    UnsupportedVersion(u32),
+    Truncated,
+    // checksum stored, checksum of the content
+    ChecksumMismatch(u64, u64),
+    InvalidData(String),
+}
+
+impl fmt::Display for HashtablesFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashtablesFileError::Io(error) => write!(f, "{}", error),
+            HashtablesFileError::NotAHashtablesFile => {
+                write!(f, "the file doesn't contain hashtables")
+            }
+            HashtablesFileError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported version {}, expected {}",
+                version, FILE_VERSION
+            ),
+            HashtablesFileError::Truncated => write!(f, "the file is truncated"),
+            HashtablesFileError::ChecksumMismatch(stored, computed) => write!(
+                f,
+                "checksum mismatch, the file is corrupted (stored {:#018x}, computed {:#018x})",
+                stored, computed
+            ),
+            HashtablesFileError::InvalidData(reason) => write!(f, "invalid data: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for HashtablesFileError {}
+
+impl From<io::Error> for HashtablesFileError {
+    fn from(error: io::Error) -> HashtablesFileError {
+        HashtablesFileError::Io(error)
+    }
+}
+
+fn get_checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N], HashtablesFileError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or(HashtablesFileError::Truncated)?;
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, HashtablesFileError> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, HashtablesFileError> {
+        Ok(u32::from_le_bytes(self.read_bytes()?))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, HashtablesFileError> {
+        Ok(u64::from_le_bytes(self.read_bytes()?))
+    }
+
+    fn read_masks(&mut self, masks: &mut [u64]) -> Result<(), HashtablesFileError> {
+        for mask in masks.iter_mut() {
+            *mask = self.read_u64()?;
+        }
+        Ok(())
+    }
+
+    fn read_magic_entries(&mut self) -> Result<[MagicEntry; 64], HashtablesFileError> {
+        let mut magic_entries = [MagicEntry {
+            mask: 0,
+            magic_number: 0,
+            shift: 0,
+            offset: 0,
+        }; 64];
+        for magic_entry in magic_entries.iter_mut() {
+            *magic_entry = MagicEntry {
+                mask: self.read_u64()?,
+                magic_number: self.read_u64()?,
+                shift: self.read_u8()?,
+                offset: self.read_u32()?,
+            };
+        }
+        Ok(magic_entries)
+    }
+}
+
+// the lookups index slider_attacks with the entries so they must stay in bounds
+fn check_magic_entries(
+    magic_entries: &[MagicEntry; 64],
+    moves_masks: &[u64; 64],
+    slider_attacks_len: usize,
+) -> Result<(), HashtablesFileError> {
+    for (i, magic_entry) in magic_entries.iter().enumerate() {
+        if magic_entry.mask != moves_masks[i] {
+            return Err(HashtablesFileError::InvalidData(format!(
+                "wrong mask for square {}",
+                i
+            )));
+        }
+        let bits = magic_entry.mask.count_ones();
+        if magic_entry.shift as u32 != 64 - bits {
+            return Err(HashtablesFileError::InvalidData(format!(
+                "shift {} of square {} for a mask of {} squares",
+                magic_entry.shift, i, bits
+            )));
+        }
+        if magic_entry.offset as usize + (1 << bits) > slider_attacks_len {
+            return Err(HashtablesFileError::InvalidData(format!(
+                "attacks of square {} out of the slider attacks",
+                i
+            )));
+        }
+    }
+    Ok(())
+}
+
+// the tables that don't depend on the magic numbers can only have one content
+fn check_table<T: PartialEq>(
+    name: &str,
+    table: &T,
+    generated_table: &T,
+) -> Result<(), HashtablesFileError> {
+    if table != generated_table {
+        return Err(HashtablesFileError::InvalidData(format!(
+            "the {} don't match the generated ones",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn write_magic_entries(bytes: &mut Vec<u8>, magic_entries: &[MagicEntry; 64]) {
+    for magic_entry in magic_entries {
+        bytes.extend(magic_entry.mask.to_le_bytes());
+        bytes.extend(magic_entry.magic_number.to_le_bytes());
+        bytes.push(magic_entry.shift);
+        bytes.extend(magic_entry.offset.to_le_bytes());
+    }
+}
+
+fn write_masks(bytes: &mut Vec<u8>, masks: &[u64]) {
+    for mask in masks {
+        bytes.extend(mask.to_le_bytes());
+    }
+}
+
+impl MainHashtables {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), HashtablesFileError> {
+        let mut bytes = Vec::new();
+        bytes.extend(FILE_SIGNATURE);
+        bytes.extend(FILE_VERSION.to_le_bytes());
+        bytes.push(match self.get_slider_backend() {
+            SliderBackend::Magic => 0,
+            SliderBackend::Pext => 1,
+        });
+        write_magic_entries(&mut bytes, &self.rook_moves_masks_magical_numbers);
+        write_magic_entries(&mut bytes, &self.bishop_moves_masks_magical_numbers);
+        bytes.extend((self.slider_attacks.len() as u64).to_le_bytes());
+        write_masks(&mut bytes, &self.slider_attacks);
+        write_masks(&mut bytes, &self.knight_move_masks);
+        write_masks(&mut bytes, self.pawn_mask_takes_hashmaps.as_flattened());
+        write_masks(
+            &mut bytes,
+            self.pawn_mask_blockers_hashmaps
+                .as_flattened()
+                .as_flattened(),
+        );
+        bytes.extend(self.pawn_offsets.as_flattened().as_flattened());
+        write_masks(&mut bytes, &self.king_move_masks);
+        bytes.extend(get_checksum(&bytes).to_le_bytes());
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    // a file saved with the pext backend on a cpu without bmi2 is loaded with the magic backend
+    pub fn load(path: impl AsRef<Path>) -> Result<MainHashtables, HashtablesFileError> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < HEADER_SIZE || &bytes[..FILE_SIGNATURE.len()] != FILE_SIGNATURE {
+            return Err(HashtablesFileError::NotAHashtablesFile);
+        }
+        let mut reader = Reader {
+            bytes: &bytes,
+            position: FILE_SIGNATURE.len(),
+        };
+        let version = reader.read_u32()?;
+        if version != FILE_VERSION {
+            return Err(HashtablesFileError::UnsupportedVersion(version));
+        }
+        if bytes.len() < HEADER_SIZE + 8 {
+            return Err(HashtablesFileError::Truncated);
+        }
+        let (content, checksum) = bytes.split_at(bytes.len() - 8);
+        let checksum = u64::from_le_bytes(checksum.try_into().unwrap());
+        let content_checksum = get_checksum(content);
+        if content_checksum != checksum {
+            return Err(HashtablesFileError::ChecksumMismatch(
+                checksum,
+                content_checksum,
+            ));
+        }
+        reader.bytes = content;
+
+        let slider_backend = match reader.read_u8()? {
+            0 => SliderBackend::Magic,
+            1 => SliderBackend::Pext,
+            backend => {
+                return Err(HashtablesFileError::InvalidData(format!(
+                    "unknown slider backend {}",
+                    backend
+                )));
+            }
+        };
+        let rook_moves_masks_magical_numbers = reader.read_magic_entries()?;
+        let bishop_moves_masks_magical_numbers = reader.read_magic_entries()?;
+        let slider_attacks_len = reader.read_u64()? as usize;
+        // checked before allocating so a corrupted length can't ask for too much memory
+        if slider_attacks_len > (content.len() - reader.position) / 8 {
+            return Err(HashtablesFileError::Truncated);
+        }
+        let mut slider_attacks = vec![0; slider_attacks_len];
+        reader.read_masks(&mut slider_attacks)?;
+        check_magic_entries(
+            &rook_moves_masks_magical_numbers,
+            &get_rook_moves_masks(),
+            slider_attacks_len,
+        )?;
+        check_magic_entries(
+            &bishop_moves_masks_magical_numbers,
+            &get_bishop_moves_masks(),
+            slider_attacks_len,
+        )?;
+        // the magic order is rebuilt whatever the backend, set_slider_backend relies on it
+        let magic_slider_attacks = get_slider_attacks(
+            &rook_moves_masks_magical_numbers,
+            &bishop_moves_masks_magical_numbers,
+            SliderBackend::Magic,
+        )
+        .ok_or_else(|| {
+            HashtablesFileError::InvalidData(String::from(
+                "the magic numbers don't fit the slider attacks",
+            ))
+        })?;
+        let generated_slider_attacks = match slider_backend {
+            SliderBackend::Magic => Some(magic_slider_attacks),
+            SliderBackend::Pext => get_slider_attacks(
+                &rook_moves_masks_magical_numbers,
+                &bishop_moves_masks_magical_numbers,
+                SliderBackend::Pext,
+            ),
+        };
+        if generated_slider_attacks.as_ref() != Some(&slider_attacks) {
+            return Err(HashtablesFileError::InvalidData(String::from(
+                "the slider attacks don't match the magic entries",
+            )));
+        }
+
+        let mut knight_move_masks = [0; 64];
+        reader.read_masks(&mut knight_move_masks)?;
+        let mut pawn_mask_takes_hashmaps = [[0; 64]; 2];
+        reader.read_masks(pawn_mask_takes_hashmaps.as_flattened_mut())?;
+        let mut pawn_mask_blockers_hashmaps = [[[0; 4]; 64]; 2];
+        reader.read_masks(
+            pawn_mask_blockers_hashmaps
+                .as_flattened_mut()
+                .as_flattened_mut(),
+        )?;
+        let mut pawn_offsets = [[[0; 2]; 64]; 2];
+        for offset in pawn_offsets.as_flattened_mut().as_flattened_mut() {
+            *offset = reader.read_u8()?;
+        }
+        let mut king_move_masks = [0; 64];
+        reader.read_masks(&mut king_move_masks)?;
+        if reader.position != content.len() {
+            return Err(HashtablesFileError::InvalidData(String::from(
+                "unexpected bytes after the tables",
+            )));
+        }
+        check_table(
+            "knight moves",
+            &knight_move_masks,
+            &get_knight_moves_masks(),
+        )?;
+        check_table(
+            "pawn takes",
+            &pawn_mask_takes_hashmaps,
+            &get_pawn_takes_hashmaps(),
+        )?;
+        check_table(
+            "pawn blockers",
+            &pawn_mask_blockers_hashmaps,
+            &get_pawn_mask_blockers_hashmaps(),
+        )?;
+        check_table("pawn offsets", &pawn_offsets, &get_pawn_offsets())?;
+        check_table("king moves", &king_move_masks, &get_king_moves_masks())?;
+
+        let mut ma = MainHashtables {
+            slider_attacks,
+            slider_backend,
+            rook_moves_masks_magical_numbers,
+            bishop_moves_masks_magical_numbers,
+            knight_move_masks,
+            pawn_mask_takes_hashmaps,
+            pawn_mask_blockers_hashmaps,
+            pawn_offsets,
+            king_move_masks,
+        };
+        if !slider_backend.is_available() {
+            ma.set_slider_backend(SliderBackend::Magic);
+        }
+        Ok(ma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+
+    fn get_temporary_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pomme_chess_{}_{}", std::process::id(), name))
+    }
+
+    // the checksum is recomputed so only the checks of the content can reject the file
+    fn write_with_checksum(path: &Path, mut bytes: Vec<u8>) {
+        let content_len = bytes.len() - 8;
+        let checksum = get_checksum(&bytes[..content_len]);
+        bytes[content_len..].copy_from_slice(&checksum.to_le_bytes());
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn saved_hashtables_are_loaded_back() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let path = get_temporary_path("saved_hashtables_are_loaded_back");
+        ma.save(&path).unwrap();
+        let loaded_ma = MainHashtables::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded_ma.get_slider_backend(), ma.get_slider_backend());
+        assert_eq!(loaded_ma.slider_attacks, ma.slider_attacks);
+        for i in 0..64 {
+            for (loaded_magic_entries, magic_entries) in [
+                (
+                    &loaded_ma.rook_moves_masks_magical_numbers,
+                    &ma.rook_moves_masks_magical_numbers,
+                ),
+                (
+                    &loaded_ma.bishop_moves_masks_magical_numbers,
+                    &ma.bishop_moves_masks_magical_numbers,
+                ),
+            ] {
+                assert_eq!(loaded_magic_entries[i].mask, magic_entries[i].mask);
+                assert_eq!(
+                    loaded_magic_entries[i].magic_number,
+                    magic_entries[i].magic_number
+                );
+                assert_eq!(loaded_magic_entries[i].shift, magic_entries[i].shift);
+                assert_eq!(loaded_magic_entries[i].offset, magic_entries[i].offset);
+            }
+        }
+        assert_eq!(loaded_ma.knight_move_masks, ma.knight_move_masks);
+        assert_eq!(
+            loaded_ma.pawn_mask_takes_hashmaps,
+            ma.pawn_mask_takes_hashmaps
+        );
+        assert_eq!(
+            loaded_ma.pawn_mask_blockers_hashmaps,
+            ma.pawn_mask_blockers_hashmaps
+        );
+        assert_eq!(loaded_ma.pawn_offsets, ma.pawn_offsets);
+        assert_eq!(loaded_ma.king_move_masks, ma.king_move_masks);
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let path = get_temporary_path("invalid_files_are_rejected");
+        ma.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        let mut corrupted = bytes.clone();
+        corrupted[1000] ^= 1;
+        fs::write(&path, &corrupted).unwrap();
+        let error = MainHashtables::load(&path).err().unwrap();
+        assert!(matches!(error, HashtablesFileError::ChecksumMismatch(_, _)));
+
+        let mut other_version = bytes.clone();
+        other_version[FILE_SIGNATURE.len()] += 1;
+        fs::write(&path, &other_version).unwrap();
+        let error = MainHashtables::load(&path).err().unwrap();
+        assert!(matches!(
+            error,
+            HashtablesFileError::UnsupportedVersion(version) if version == FILE_VERSION + 1
+        ));
+
+        fs::write(&path, &bytes[..HEADER_SIZE + 4]).unwrap();
+        let error = MainHashtables::load(&path).err().unwrap();
+        assert!(matches!(error, HashtablesFileError::Truncated));
+
+        // the first rook mask covering the whole board
+        let mut full_mask = bytes.clone();
+        let magic_entries_position = HEADER_SIZE + 1;
+        full_mask[magic_entries_position..magic_entries_position + 8].fill(0xff);
+        full_mask[magic_entries_position + 16] = 0;
+        write_with_checksum(&path, full_mask);
+        let error = MainHashtables::load(&path).err().unwrap();
+        assert!(matches!(error, HashtablesFileError::InvalidData(_)));
+
+        fs::write(&path, b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+        let error = MainHashtables::load(&path).err().unwrap();
+        assert!(matches!(error, HashtablesFileError::NotAHashtablesFile));
+
+        fs::remove_file(&path).unwrap();
+        let error = MainHashtables::load(&path).err().unwrap();
+        assert!(matches!(error, HashtablesFileError::Io(_)));
+    }
+
+    #[test]
+    fn wrong_magic_numbers_are_rejected() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let mut rook_magic_entries = ma.rook_moves_masks_magical_numbers;
+        rook_magic_entries[0].magic_number = 0;
+        assert!(
+            get_slider_attacks(
+                &rook_magic_entries,
+                &ma.bishop_moves_masks_magical_numbers,
+                SliderBackend::Magic
+            )
+            .is_none()
+        );
+        let mut bishop_magic_entries = ma.bishop_moves_masks_magical_numbers;
+        bishop_magic_entries[0].offset += 1;
+        assert!(
+            get_slider_attacks(
+                &ma.rook_moves_masks_magical_numbers,
+                &bishop_magic_entries,
+                SliderBackend::Pext
+            )
+            .is_none()
+        );
+
+        // the magic number of the first rook entry set to 0
+        let path = get_temporary_path("wrong_magic_numbers_are_rejected");
+        ma.save(&path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        let magic_number_position = HEADER_SIZE + 1 + 8;
+        bytes[magic_number_position..magic_number_position + 8].fill(0);
+        write_with_checksum(&path, bytes);
+        let error = MainHashtables::load(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(error, HashtablesFileError::InvalidData(_)));
+    }
+
+    #[test]
+    fn tables_not_matching_the_generated_ones_are_rejected() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let path = get_temporary_path("tables_not_matching_the_generated_ones_are_rejected");
+        ma.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        // sizes in bytes, the tables after the slider attacks are at the end of the content
+        let content_len = bytes.len() - 8;
+        let king_position = content_len - 64 * 8;
+        let pawn_offsets_position = king_position - 2 * 64 * 2;
+        let pawn_blockers_position = pawn_offsets_position - 2 * 64 * 4 * 8;
+        let pawn_takes_position = pawn_blockers_position - 2 * 64 * 8;
+        let knight_position = pawn_takes_position - 64 * 8;
+        let slider_attacks_position = HEADER_SIZE + 1 + 2 * 64 * 21 + 8;
+        for position in [
+            slider_attacks_position,
+            knight_position,
+            pawn_takes_position,
+            pawn_blockers_position,
+            pawn_offsets_position,
+            king_position,
+        ] {
+            let mut corrupted = bytes.clone();
+            corrupted[position] ^= 1;
+            write_with_checksum(&path, corrupted);
+            let error = MainHashtables::load(&path).err().unwrap();
+            assert!(
+                matches!(error, HashtablesFileError::InvalidData(_)),
+                "{}",
+                position
+            );
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,776 @@
+// generated by `pomme_chess magic-gen`, do not edit by hand
+use crate::binary_mask::MagicEntry;
+
+pub const ROOK_MAGIC_ENTRIES: [MagicEntry; 64] = [
+    MagicEntry {
+        mask: 0x000101010101017e,
+        magic_number: 0x2080102080004003,
+        shift: 52,
+        offset: 0,
+    },
+    MagicEntry {
+        mask: 0x000202020202027c,
+        magic_number: 0x0240002000401000,
+        shift: 53,
+        offset: 4096,
+    },
+    MagicEntry {
+        mask: 0x000404040404047a,
+        magic_number: 0x0a00108008204200,
+        shift: 53,
+        offset: 6144,
+    },
+    MagicEntry {
+        mask: 0x0008080808080876,
+        magic_number: 0x2080049001080080,
+        shift: 53,
+        offset: 8192,
+    },
+    MagicEntry {
+        mask: 0x001010101010106e,
+        magic_number: 0x0a00204884101600,
+        shift: 53,
+        offset: 10240,
+    },
+    MagicEntry {
+        mask: 0x002020202020205e,
+        magic_number: 0x3200081004010200,
+        shift: 53,
+        offset: 12288,
+    },
+    MagicEntry {
+        mask: 0x004040404040403e,
+        magic_number: 0x4880020025000280,
+        shift: 53,
+        offset: 14336,
+    },
+    MagicEntry {
+        mask: 0x008080808080807e,
+        magic_number: 0x0200008204250044,
+        shift: 52,
+        offset: 16384,
+    },
+    MagicEntry {
+        mask: 0x0001010101017e00,
+        magic_number: 0x2040800030400088,
+        shift: 53,
+        offset: 20480,
+    },
+    MagicEntry {
+        mask: 0x0002020202027c00,
+        magic_number: 0x0000804000200080,
+        shift: 54,
+        offset: 22528,
+    },
+    MagicEntry {
+        mask: 0x0004040404047a00,
+        magic_number: 0x0010808020001000,
+        shift: 54,
+        offset: 23552,
+    },
+    MagicEntry {
+        mask: 0x0008080808087600,
+        magic_number: 0x00c0801000800800,
+        shift: 54,
+        offset: 24576,
+    },
+    MagicEntry {
+        mask: 0x0010101010106e00,
+        magic_number: 0x0005001008010204,
+        shift: 54,
+        offset: 25600,
+    },
+    MagicEntry {
+        mask: 0x0020202020205e00,
+        magic_number: 0x0a1a001102000804,
+        shift: 54,
+        offset: 26624,
+    },
+    MagicEntry {
+        mask: 0x0040404040403e00,
+        magic_number: 0x8404002842300134,
+        shift: 54,
+        offset: 27648,
+    },
+    MagicEntry {
+        mask: 0x0080808080807e00,
+        magic_number: 0x1002000084440d2a,
+        shift: 53,
+        offset: 28672,
+    },
+    MagicEntry {
+        mask: 0x00010101017e0100,
+        magic_number: 0x04008280004002a0,
+        shift: 53,
+        offset: 30720,
+    },
+    MagicEntry {
+        mask: 0x00020202027c0200,
+        magic_number: 0x0081010020400082,
+        shift: 54,
+        offset: 32768,
+    },
+    MagicEntry {
+        mask: 0x00040404047a0400,
+        magic_number: 0x4003310041042000,
+        shift: 54,
+        offset: 33792,
+    },
+    MagicEntry {
+        mask: 0x0008080808760800,
+        magic_number: 0x2050008008001080,
+        shift: 54,
+        offset: 34816,
+    },
+    MagicEntry {
+        mask: 0x00101010106e1000,
+        magic_number: 0x0128008004000a80,
+        shift: 54,
+        offset: 35840,
+    },
+    MagicEntry {
+        mask: 0x00202020205e2000,
+        magic_number: 0x0c04008004020080,
+        shift: 54,
+        offset: 36864,
+    },
+    MagicEntry {
+        mask: 0x00404040403e4000,
+        magic_number: 0x00a8040008100102,
+        shift: 54,
+        offset: 37888,
+    },
+    MagicEntry {
+        mask: 0x00808080807e8000,
+        magic_number: 0x0000020000440081,
+        shift: 53,
+        offset: 38912,
+    },
+    MagicEntry {
+        mask: 0x000101017e010100,
+        magic_number: 0x8010400a80038020,
+        shift: 53,
+        offset: 40960,
+    },
+    MagicEntry {
+        mask: 0x000202027c020200,
+        magic_number: 0x0800400080200081,
+        shift: 54,
+        offset: 43008,
+    },
+    MagicEntry {
+        mask: 0x000404047a040400,
+        magic_number: 0x8800100080200082,
+        shift: 54,
+        offset: 44032,
+    },
+    MagicEntry {
+        mask: 0x0008080876080800,
+        magic_number: 0x0a00100080080080,
+        shift: 54,
+        offset: 45056,
+    },
+    MagicEntry {
+        mask: 0x001010106e101000,
+        magic_number: 0x0818040080800800,
+        shift: 54,
+        offset: 46080,
+    },
+    MagicEntry {
+        mask: 0x002020205e202000,
+        magic_number: 0x0040040080020080,
+        shift: 54,
+        offset: 47104,
+    },
+    MagicEntry {
+        mask: 0x004040403e404000,
+        magic_number: 0x0040010400880250,
+        shift: 54,
+        offset: 48128,
+    },
+    MagicEntry {
+        mask: 0x008080807e808000,
+        magic_number: 0x8104004a000400b1,
+        shift: 53,
+        offset: 49152,
+    },
+    MagicEntry {
+        mask: 0x0001017e01010100,
+        magic_number: 0x0040204000800080,
+        shift: 53,
+        offset: 51200,
+    },
+    MagicEntry {
+        mask: 0x0002027c02020200,
+        magic_number: 0x4800802004804000,
+        shift: 54,
+        offset: 53248,
+    },
+    MagicEntry {
+        mask: 0x0004047a04040400,
+        magic_number: 0x9020001000802084,
+        shift: 54,
+        offset: 54272,
+    },
+    MagicEntry {
+        mask: 0x0008087608080800,
+        magic_number: 0x0280100080800800,
+        shift: 54,
+        offset: 55296,
+    },
+    MagicEntry {
+        mask: 0x0010106e10101000,
+        magic_number: 0x0000800400800800,
+        shift: 54,
+        offset: 56320,
+    },
+    MagicEntry {
+        mask: 0x0020205e20202000,
+        magic_number: 0x2024040080800200,
+        shift: 54,
+        offset: 57344,
+    },
+    MagicEntry {
+        mask: 0x0040403e40404000,
+        magic_number: 0x0000020001010004,
+        shift: 54,
+        offset: 58368,
+    },
+    MagicEntry {
+        mask: 0x0080807e80808000,
+        magic_number: 0x4811006102001084,
+        shift: 53,
+        offset: 59392,
+    },
+    MagicEntry {
+        mask: 0x00017e0101010100,
+        magic_number: 0x8000208040008000,
+        shift: 53,
+        offset: 61440,
+    },
+    MagicEntry {
+        mask: 0x00027c0202020200,
+        magic_number: 0x0c20100040004022,
+        shift: 54,
+        offset: 63488,
+    },
+    MagicEntry {
+        mask: 0x00047a0404040400,
+        magic_number: 0x2010002000808010,
+        shift: 54,
+        offset: 64512,
+    },
+    MagicEntry {
+        mask: 0x0008760808080800,
+        magic_number: 0x0000220208420010,
+        shift: 54,
+        offset: 65536,
+    },
+    MagicEntry {
+        mask: 0x00106e1010101000,
+        magic_number: 0x0001004800050010,
+        shift: 54,
+        offset: 66560,
+    },
+    MagicEntry {
+        mask: 0x00205e2020202000,
+        magic_number: 0x0021008400090002,
+        shift: 54,
+        offset: 67584,
+    },
+    MagicEntry {
+        mask: 0x00403e4040404000,
+        magic_number: 0x0202000188020004,
+        shift: 54,
+        offset: 68608,
+    },
+    MagicEntry {
+        mask: 0x00807e8080808000,
+        magic_number: 0x8405000284430022,
+        shift: 53,
+        offset: 69632,
+    },
+    MagicEntry {
+        mask: 0x007e010101010100,
+        magic_number: 0x0090800420400280,
+        shift: 53,
+        offset: 71680,
+    },
+    MagicEntry {
+        mask: 0x007c020202020200,
+        magic_number: 0x0404310040820200,
+        shift: 54,
+        offset: 73728,
+    },
+    MagicEntry {
+        mask: 0x007a040404040400,
+        magic_number: 0x8000102840820200,
+        shift: 54,
+        offset: 74752,
+    },
+    MagicEntry {
+        mask: 0x0076080808080800,
+        magic_number: 0x0000100008008480,
+        shift: 54,
+        offset: 75776,
+    },
+    MagicEntry {
+        mask: 0x006e101010101000,
+        magic_number: 0x0002510008004500,
+        shift: 54,
+        offset: 76800,
+    },
+    MagicEntry {
+        mask: 0x005e202020202000,
+        magic_number: 0x1148040080020080,
+        shift: 54,
+        offset: 77824,
+    },
+    MagicEntry {
+        mask: 0x003e404040404000,
+        magic_number: 0x0080180230110400,
+        shift: 54,
+        offset: 78848,
+    },
+    MagicEntry {
+        mask: 0x007e808080808000,
+        magic_number: 0xa000010060840200,
+        shift: 53,
+        offset: 79872,
+    },
+    MagicEntry {
+        mask: 0x7e01010101010100,
+        magic_number: 0x010015420100a082,
+        shift: 52,
+        offset: 81920,
+    },
+    MagicEntry {
+        mask: 0x7c02020202020200,
+        magic_number: 0x0004110840002081,
+        shift: 53,
+        offset: 86016,
+    },
+    MagicEntry {
+        mask: 0x7a04040404040400,
+        magic_number: 0x0040102004400901,
+        shift: 53,
+        offset: 88064,
+    },
+    MagicEntry {
+        mask: 0x7608080808080800,
+        magic_number: 0x000a200901041001,
+        shift: 53,
+        offset: 90112,
+    },
+    MagicEntry {
+        mask: 0x6e10101010101000,
+        magic_number: 0x0001000800029005,
+        shift: 53,
+        offset: 92160,
+    },
+    MagicEntry {
+        mask: 0x5e20202020202000,
+        magic_number: 0x94a3001804000a05,
+        shift: 53,
+        offset: 94208,
+    },
+    MagicEntry {
+        mask: 0x3e40404040404000,
+        magic_number: 0x020815081002019c,
+        shift: 53,
+        offset: 96256,
+    },
+    MagicEntry {
+        mask: 0x7e80808080808000,
+        magic_number: 0x9000003100840042,
+        shift: 52,
+        offset: 98304,
+    },
+];
+
+pub const BISHOP_MAGIC_ENTRIES: [MagicEntry; 64] = [
+    MagicEntry {
+        mask: 0x0040201008040200,
+        magic_number: 0x0804a00084010048,
+        shift: 58,
+        offset: 102400,
+    },
+    MagicEntry {
+        mask: 0x0000402010080400,
+        magic_number: 0x0010411104108004,
+        shift: 59,
+        offset: 102464,
+    },
+    MagicEntry {
+        mask: 0x0000004020100a00,
+        magic_number: 0x0004114401000402,
+        shift: 59,
+        offset: 102496,
+    },
+    MagicEntry {
+        mask: 0x0000000040221400,
+        magic_number: 0x2014440288401100,
+        shift: 59,
+        offset: 102528,
+    },
+    MagicEntry {
+        mask: 0x0000000002442800,
+        magic_number: 0x2044042086400010,
+        shift: 59,
+        offset: 102560,
+    },
+    MagicEntry {
+        mask: 0x0000000204085000,
+        magic_number: 0x0001040240005308,
+        shift: 59,
+        offset: 102592,
+    },
+    MagicEntry {
+        mask: 0x0000020408102000,
+        magic_number: 0x4000411420208004,
+        shift: 59,
+        offset: 102624,
+    },
+    MagicEntry {
+        mask: 0x0002040810204000,
+        magic_number: 0x2000140404040400,
+        shift: 58,
+        offset: 102656,
+    },
+    MagicEntry {
+        mask: 0x0020100804020000,
+        magic_number: 0xc001101430488204,
+        shift: 59,
+        offset: 102720,
+    },
+    MagicEntry {
+        mask: 0x0040201008040000,
+        magic_number: 0x0002021022008104,
+        shift: 59,
+        offset: 102752,
+    },
+    MagicEntry {
+        mask: 0x00004020100a0000,
+        magic_number: 0x20004200a2008000,
+        shift: 59,
+        offset: 102784,
+    },
+    MagicEntry {
+        mask: 0x0000004022140000,
+        magic_number: 0x0040041042004400,
+        shift: 59,
+        offset: 102816,
+    },
+    MagicEntry {
+        mask: 0x0000000244280000,
+        magic_number: 0x0108040420029000,
+        shift: 59,
+        offset: 102848,
+    },
+    MagicEntry {
+        mask: 0x0000020408500000,
+        magic_number: 0x1600421510081000,
+        shift: 59,
+        offset: 102880,
+    },
+    MagicEntry {
+        mask: 0x0002040810200000,
+        magic_number: 0x00020601011840c0,
+        shift: 59,
+        offset: 102912,
+    },
+    MagicEntry {
+        mask: 0x0004081020400000,
+        magic_number: 0x0008088044100480,
+        shift: 59,
+        offset: 102944,
+    },
+    MagicEntry {
+        mask: 0x0010080402000200,
+        magic_number: 0x0008021102104400,
+        shift: 59,
+        offset: 102976,
+    },
+    MagicEntry {
+        mask: 0x0020100804000400,
+        magic_number: 0x0020001044208080,
+        shift: 59,
+        offset: 103008,
+    },
+    MagicEntry {
+        mask: 0x004020100a000a00,
+        magic_number: 0x004040480c890110,
+        shift: 57,
+        offset: 103040,
+    },
+    MagicEntry {
+        mask: 0x0000402214001400,
+        magic_number: 0x0002006020204080,
+        shift: 57,
+        offset: 103168,
+    },
+    MagicEntry {
+        mask: 0x0000024428002800,
+        magic_number: 0xa404000482e00280,
+        shift: 57,
+        offset: 103296,
+    },
+    MagicEntry {
+        mask: 0x0002040850005000,
+        magic_number: 0x1002000840422040,
+        shift: 57,
+        offset: 103424,
+    },
+    MagicEntry {
+        mask: 0x0004081020002000,
+        magic_number: 0x304a484402082491,
+        shift: 59,
+        offset: 103552,
+    },
+    MagicEntry {
+        mask: 0x0008102040004000,
+        magic_number: 0x04004401240a2820,
+        shift: 59,
+        offset: 103584,
+    },
+    MagicEntry {
+        mask: 0x0008040200020400,
+        magic_number: 0x4020888020024410,
+        shift: 59,
+        offset: 103616,
+    },
+    MagicEntry {
+        mask: 0x0010080400040800,
+        magic_number: 0x00100a0148880712,
+        shift: 59,
+        offset: 103648,
+    },
+    MagicEntry {
+        mask: 0x0020100a000a1000,
+        magic_number: 0x2000404308008102,
+        shift: 57,
+        offset: 103680,
+    },
+    MagicEntry {
+        mask: 0x0040221400142200,
+        magic_number: 0x4808080020220020,
+        shift: 55,
+        offset: 103808,
+    },
+    MagicEntry {
+        mask: 0x0002442800284400,
+        magic_number: 0x0002040202008200,
+        shift: 55,
+        offset: 104320,
+    },
+    MagicEntry {
+        mask: 0x0004085000500800,
+        magic_number: 0x0048020082220500,
+        shift: 57,
+        offset: 104832,
+    },
+    MagicEntry {
+        mask: 0x0008102000201000,
+        magic_number: 0x1302060100882100,
+        shift: 59,
+        offset: 104960,
+    },
+    MagicEntry {
+        mask: 0x0010204000402000,
+        magic_number: 0x0601004047005800,
+        shift: 59,
+        offset: 104992,
+    },
+    MagicEntry {
+        mask: 0x0004020002040800,
+        magic_number: 0x1004504002044400,
+        shift: 59,
+        offset: 105024,
+    },
+    MagicEntry {
+        mask: 0x0008040004081000,
+        magic_number: 0x0804300800040124,
+        shift: 59,
+        offset: 105056,
+    },
+    MagicEntry {
+        mask: 0x00100a000a102000,
+        magic_number: 0x0000208800101020,
+        shift: 57,
+        offset: 105088,
+    },
+    MagicEntry {
+        mask: 0x0022140014224000,
+        magic_number: 0x6c00200800250104,
+        shift: 55,
+        offset: 105216,
+    },
+    MagicEntry {
+        mask: 0x0044280028440200,
+        magic_number: 0x8048020400001100,
+        shift: 55,
+        offset: 105728,
+    },
+    MagicEntry {
+        mask: 0x0008500050080400,
+        magic_number: 0x00200049000080a0,
+        shift: 57,
+        offset: 106240,
+    },
+    MagicEntry {
+        mask: 0x0010200020100800,
+        magic_number: 0x23011a1208008800,
+        shift: 59,
+        offset: 106368,
+    },
+    MagicEntry {
+        mask: 0x0020400040201000,
+        magic_number: 0x80240c5200084310,
+        shift: 59,
+        offset: 106400,
+    },
+    MagicEntry {
+        mask: 0x0002000204081000,
+        magic_number: 0x008101904000d000,
+        shift: 59,
+        offset: 106432,
+    },
+    MagicEntry {
+        mask: 0x0004000408102000,
+        magic_number: 0x4000410820200801,
+        shift: 59,
+        offset: 106464,
+    },
+    MagicEntry {
+        mask: 0x000a000a10204000,
+        magic_number: 0x0000840048010100,
+        shift: 57,
+        offset: 106496,
+    },
+    MagicEntry {
+        mask: 0x0014001422400000,
+        magic_number: 0x5400004010413200,
+        shift: 57,
+        offset: 106624,
+    },
+    MagicEntry {
+        mask: 0x0028002844020000,
+        magic_number: 0x902004410c004200,
+        shift: 57,
+        offset: 106752,
+    },
+    MagicEntry {
+        mask: 0x0050005008040200,
+        magic_number: 0x0e40080800208040,
+        shift: 57,
+        offset: 106880,
+    },
+    MagicEntry {
+        mask: 0x0020002010080400,
+        magic_number: 0x1024c40c00400400,
+        shift: 59,
+        offset: 107008,
+    },
+    MagicEntry {
+        mask: 0x0040004020100800,
+        magic_number: 0x6401042400846248,
+        shift: 59,
+        offset: 107040,
+    },
+    MagicEntry {
+        mask: 0x0000020408102000,
+        magic_number: 0x203a014918401600,
+        shift: 59,
+        offset: 107072,
+    },
+    MagicEntry {
+        mask: 0x0000040810204000,
+        magic_number: 0x8000220114602820,
+        shift: 59,
+        offset: 107104,
+    },
+    MagicEntry {
+        mask: 0x00000a1020400000,
+        magic_number: 0x4214288404290624,
+        shift: 59,
+        offset: 107136,
+    },
+    MagicEntry {
+        mask: 0x0000142240000000,
+        magic_number: 0x7019048c84041880,
+        shift: 59,
+        offset: 107168,
+    },
+    MagicEntry {
+        mask: 0x0000284402000000,
+        magic_number: 0x5010020820884000,
+        shift: 59,
+        offset: 107200,
+    },
+    MagicEntry {
+        mask: 0x0000500804020000,
+        magic_number: 0x00002002040840c0,
+        shift: 59,
+        offset: 107232,
+    },
+    MagicEntry {
+        mask: 0x0000201008040200,
+        magic_number: 0xc084054808210000,
+        shift: 59,
+        offset: 107264,
+    },
+    MagicEntry {
+        mask: 0x0000402010080400,
+        magic_number: 0x001004010c082020,
+        shift: 59,
+        offset: 107296,
+    },
+    MagicEntry {
+        mask: 0x0002040810204000,
+        magic_number: 0x0100218844104004,
+        shift: 58,
+        offset: 107328,
+    },
+    MagicEntry {
+        mask: 0x0004081020400000,
+        magic_number: 0x0000020101080280,
+        shift: 59,
+        offset: 107392,
+    },
+    MagicEntry {
+        mask: 0x000a102040000000,
+        magic_number: 0x9000000104210404,
+        shift: 59,
+        offset: 107424,
+    },
+    MagicEntry {
+        mask: 0x0014224000000000,
+        magic_number: 0x2124080000420200,
+        shift: 59,
+        offset: 107456,
+    },
+    MagicEntry {
+        mask: 0x0028440200000000,
+        magic_number: 0x2020010040050111,
+        shift: 59,
+        offset: 107488,
+    },
+    MagicEntry {
+        mask: 0x0050080402000000,
+        magic_number: 0x0000200820480080,
+        shift: 59,
+        offset: 107520,
+    },
+    MagicEntry {
+        mask: 0x0020100804020000,
+        magic_number: 0x0000600204290400,
+        shift: 59,
+        offset: 107552,
+    },
+    MagicEntry {
+        mask: 0x0040201008040200,
+        magic_number: 0x0058820884090200,
+        shift: 58,
+        offset: 107584,
+    },
+];
@@ -1,12 +1,26 @@
-use crate::{binary_mask::print_mask, get_moves::MovesStruct};
-
+pub mod attacks;
 pub mod binary_mask;
+pub mod chess_move;
+pub mod evaluate;
 pub mod get_moves;
+pub mod hashtables_file;
+pub mod magic_constants;
 pub mod make_move;
+pub mod move_picker;
 pub mod notation;
+pub mod outcome;
+pub mod perft;
+pub mod pgn;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod time_manager;
+pub mod transposition_table;
+pub mod uci;
+pub mod zobrist;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum TypePiece {
+pub enum TypePiece {
     WhiteKing = 0,
     WhiteQueen = 1,
     WhiteRook = 2,
@@ -22,7 +36,25 @@ enum TypePiece {
     Empty = 12,
 }
 
-struct ChessBoard {
+// TypePiece by their value, TYPE_PIECES[type_piece as usize] == type_piece
+pub const TYPE_PIECES: [TypePiece; 13] = [
+    TypePiece::WhiteKing,
+    TypePiece::WhiteQueen,
+    TypePiece::WhiteRook,
+    TypePiece::WhiteBishop,
+    TypePiece::WhiteKnight,
+    TypePiece::WhitePawn,
+    TypePiece::BlackKing,
+    TypePiece::BlackQueen,
+    TypePiece::BlackRook,
+    TypePiece::BlackBishop,
+    TypePiece::BlackKnight,
+    TypePiece::BlackPawn,
+    TypePiece::Empty,
+];
+
+#[derive(Clone)]
+pub struct ChessBoard {
     board: u64,
     players: [u64; 2],
 
@@ -36,146 +68,72 @@ struct ChessBoard {
     queen_side_castle: [bool; 2],
 
     en_passant: u64,
+
+    // number of half moves since the last capture or pawn move, for the fifty-move rule
+    halfmove_clock: u16,
+    // starts at 1 and is incremented after each black move
+    fullmove_number: u16,
+
+    undo_stack: Vec<make_move::UndoInfo>,
+
+    // zobrist hash of the position, see zobrist.rs
+    hash: u64,
 }
 
-fn get_starting_chessboard() -> ChessBoard {
-    let mut pieces_by_index = [TypePiece::Empty; 64];
-    // player
-    let player_rook_indexes = [56, 63];
-    let player_knight_indexes = [];
-    let player_bishop_indexes = [];
-    let player_queen_indexes = [];
-    let player_king_indexes = [60];
-    let player_pawn_indexes = [48, 49, 50, 51, 52, 53, 54, 55];
-    let mut player_board = 0;
-    for (type_piece, indexes) in [
-        (TypePiece::WhiteRook, player_rook_indexes.iter()),
-        (TypePiece::WhiteKnight, player_knight_indexes.iter()),
-        (TypePiece::WhiteBishop, player_bishop_indexes.iter()),
-        (TypePiece::WhiteQueen, player_queen_indexes.iter()),
-        (TypePiece::WhiteKing, player_king_indexes.iter()),
-        (TypePiece::WhitePawn, player_pawn_indexes.iter()),
-    ] {
-        for index in indexes {
-            player_board |= 1 << index;
-            pieces_by_index[*index as usize] = type_piece;
+// pomme_chess perft|divide <depth> [fen]
+fn run_perft_command(args: &[String]) {
+    let Some(depth) = args.get(2).and_then(|depth| depth.parse::<u8>().ok()) else {
+        eprintln!("usage: {} {} <depth> [fen]", args[0], args[1]);
+        std::process::exit(1);
+    };
+    let fen = if args.len() > 3 {
+        args[3..].join(" ")
+    } else {
+        String::from(notation::STARTING_FEN)
+    };
+    let mut chessboard = match ChessBoard::from_fen(&fen) {
+        Ok(chessboard) => chessboard,
+        Err(error) => {
+            eprintln!("invalid fen: {}", error);
+            std::process::exit(1);
         }
+    };
+    let ma = binary_mask::generate_main_hashtables(binary_mask::MagicSource::Precomputed);
+    if args[1] == "divide" {
+        perft::divide(&mut chessboard, &ma, depth);
+    } else {
+        println!("{}", perft::perft(&mut chessboard, &ma, depth));
     }
-    // opponent
-    let opponent_rook_indexes = [0, 7];
-    let opponent_knight_indexes = [];
-    let opponent_bishop_indexes = [];
-    let opponent_queen_indexes = [];
-    let opponent_king_indexes = [4];
-    let opponent_pawn_indexes = [8, 9, 10, 11, 12, 13, 14, 15];
-    let mut opponent_board = 0;
-    for (type_piece, indexes) in [
-        (TypePiece::BlackRook, opponent_rook_indexes.iter()),
-        (TypePiece::BlackKnight, opponent_knight_indexes.iter()),
-        (TypePiece::BlackBishop, opponent_bishop_indexes.iter()),
-        (TypePiece::BlackQueen, opponent_queen_indexes.iter()),
-        (TypePiece::BlackKing, opponent_king_indexes.iter()),
-        (TypePiece::BlackPawn, opponent_pawn_indexes.iter()),
-    ] {
-        for index in indexes {
-            opponent_board |= 1 << index;
-            pieces_by_index[*index as usize] = type_piece;
+}
+
+// magic-gen [seed]
+// prints the source of magic_constants.rs with magic numbers searched from the seed
+fn run_magic_gen_command(args: &[String]) {
+    let magic_seed = match args.get(2).map(|seed| seed.parse::<u64>()) {
+        None => binary_mask::DEFAULT_MAGIC_SEED,
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => {
+            eprintln!("usage: {} magic-gen [seed]", args[0]);
+            std::process::exit(1);
         }
-    }
-    return ChessBoard {
-        board: player_board | opponent_board,
-        players: [opponent_board, player_board],
-        pieces: [
-            1 << player_king_indexes[0],
-            player_queen_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            player_rook_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            player_bishop_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            player_knight_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            player_pawn_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            1 << opponent_king_indexes[0],
-            opponent_queen_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            opponent_rook_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            opponent_bishop_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            opponent_knight_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            opponent_pawn_indexes
-                .iter()
-                .map(|x| 1 << x)
-                .fold(0, |a, b| a | b),
-            0,
-        ],
-        pieces_by_index: pieces_by_index,
-        is_white_to_play: true,
-        king_side_castle: [true, true],
-        queen_side_castle: [true, true],
-        en_passant: 0,
     };
+    let ma = binary_mask::generate_main_hashtables(binary_mask::MagicSource::Search(magic_seed));
+    print!("{}", binary_mask::get_magic_constants_source(&ma));
 }
 
-fn show_current_chessboard_state(chessboard: &ChessBoard) {
-    println!("chessboard");
-    print_mask(chessboard.board);
-    println!("white pieces");
-    print_mask(chessboard.players[1]);
-    println!("black pieces");
-    print_mask(chessboard.players[0]);
-    for (i, name) in [
-        "white king",
-        "white queen",
-        "white rook",
-        "white bishop",
-        "white knight",
-        "white pawn",
-        "black king",
-        "black queen",
-        "black rook",
-        "black bishop",
-        "black knight",
-        "black pawn",
-    ]
-    .iter()
-    .enumerate()
-    {
-        println!("{}", name);
-        print_mask(chessboard.pieces[i]);
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && (args[1] == "perft" || args[1] == "divide") {
+        run_perft_command(&args);
+        return;
+    }
+    if args.len() > 1 && args[1] == "magic-gen" {
+        run_magic_gen_command(&args);
+        return;
     }
-}
 
-fn main() {
-    let ma = binary_mask::generate_main_hashtables();
-    let mut moves = MovesStruct::init();
-    let mut chessboard = get_starting_chessboard();
-    println!("{:?}", chessboard.get_fen());
-    //chessboard.make_move(16384);
-    chessboard.make_move(20480);
-    //chessboard.make_move(24576);
-    chessboard.make_move(28672);
-    chessboard.get_moves(&ma, &mut moves);
-    println!("{:?}", moves.moves);
+    uci::Uci::new(binary_mask::generate_main_hashtables(
+        binary_mask::MagicSource::Precomputed,
+    ))
+    .run();
 }
@@ -1,59 +1,232 @@
-use crate::{ChessBoard, TypePiece};
-
-// move code
-// 2 first bits = type:
-//      00  -> normal move
-//      01  -> castling
-//      10  -> promotion
-//      11  -> en-passant?
-//
-// if type == normal move:
-// just do the move, normally according to the last 12 bits
-// if type == castling:
-// do the move according to the last 12 bits, then move the rook according to the 3th->4th bits:
-//      00 -> WHITE-KINGSIDE
-//      01 -> WHITE-QUEENSIDE
-//      10 -> BLACK-KINGSIDE
-//      11 -> BLACK-QUEENSIDE
-// if type == promotion:
-// remove the pawn from_index, remove the piece at the to_index if there is one, create at the
-// to_index a piece according to the 3th->4th bits:
-//      00 -> QUEEN
-//      01 -> ROOK
-//      10 -> BISHOP
-//      11 -> KNIGHT
-
-const MAKE_MOVE_FUNCS: [for<'a> fn(&'a mut ChessBoard, u16); 4] = [
+use crate::chess_move::{CastlingSide, Move, MoveKind};
+use crate::zobrist::ZOBRIST_KEYS;
+use crate::{ChessBoard, TYPE_PIECES, TypePiece};
+
+// the layout of the move codes is described in chess_move.rs
+const MAKE_MOVE_FUNCS: [for<'a> fn(&'a mut ChessBoard, Move); 4] = [
     ChessBoard::make_move_normal,
     ChessBoard::make_move_castling,
     ChessBoard::make_move_promotion,
     ChessBoard::make_move_normal,
 ];
 
+// king_xor, rook_xor, king_from_index, king_to_index, rook_from_index, rook_to_index, color,
+// king_type, rook_type
+type CastlingInfos = (
+    u64,
+    u64,
+    usize,
+    usize,
+    usize,
+    usize,
+    usize,
+    TypePiece,
+    TypePiece,
+);
+
+pub(crate) fn get_castling_infos(side: CastlingSide) -> CastlingInfos {
+    match side {
+        CastlingSide::WhiteKingSide => (
+            0b101000000000000000000000000000000000000000000000000000000000000,
+            0b1010000000000000000000000000000000000000000000000000000000000000,
+            60,
+            62,
+            63,
+            61,
+            1,
+            TypePiece::WhiteKing,
+            TypePiece::WhiteRook,
+        ),
+        CastlingSide::WhiteQueenSide => (
+            0b1010000000000000000000000000000000000000000000000000000000000,
+            0b100100000000000000000000000000000000000000000000000000000000,
+            60,
+            58,
+            56,
+            59,
+            1,
+            TypePiece::WhiteKing,
+            TypePiece::WhiteRook,
+        ),
+        CastlingSide::BlackKingSide => (
+            0b1010000,
+            0b10100000,
+            4,
+            6,
+            7,
+            5,
+            0,
+            TypePiece::BlackKing,
+            TypePiece::BlackRook,
+        ),
+        CastlingSide::BlackQueenSide => (
+            0b10100,
+            0b1001,
+            4,
+            2,
+            0,
+            3,
+            0,
+            TypePiece::BlackKing,
+            TypePiece::BlackRook,
+        ),
+    }
+}
+
+#[derive(Clone)]
+pub struct UndoInfo {
+    move_code: Move,
+    taken_piece: TypePiece,
+    taken_index: usize,
+    king_side_castle: [bool; 2],
+    queen_side_castle: [bool; 2],
+    en_passant: u64,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+    pub(crate) hash: u64,
+}
+
 impl ChessBoard {
-    pub fn make_move(&mut self, move_code: u16) {
-        // TODO implement: castling
-        let type_move = move_code >> 14;
+    pub fn make_move(&mut self, move_code: Move) {
+        let type_move = move_code.kind();
+        let to_index = move_code.to_index();
+        let from_index = move_code.from_index();
+
+        // the state the move can't give back is saved so it can be unmade
+        let (taken_piece, taken_index) = if type_move == MoveKind::Castling {
+            (TypePiece::Empty, 0)
+        } else if self.en_passant == 1 << to_index
+            && (self.pieces_by_index[from_index] == TypePiece::WhitePawn
+                || self.pieces_by_index[from_index] == TypePiece::BlackPawn)
+        {
+            let taken_index = if self.is_white_to_play {
+                to_index + 8
+            } else {
+                to_index - 8
+            };
+            (self.pieces_by_index[taken_index], taken_index)
+        } else {
+            (self.pieces_by_index[to_index], to_index)
+        };
+        self.undo_stack.push(UndoInfo {
+            move_code,
+            taken_piece,
+            taken_index,
+            king_side_castle: self.king_side_castle,
+            queen_side_castle: self.queen_side_castle,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        });
+
+        // the make_move_* functions only update the hash for the pieces they move
+        self.hash ^= self.get_state_hash();
+        self.hash ^= ZOBRIST_KEYS.pieces[taken_piece as usize][taken_index];
         MAKE_MOVE_FUNCS[type_move as usize](self, move_code);
+        self.hash ^= self.get_state_hash();
     }
 
-    fn make_move_normal(&mut self, move_code: u16) {
-        let to_index = move_code & 0b111111;
-        let to_index = to_index as usize;
-        let from_index = (move_code >> 6) & 0b111111;
-        let from_index = from_index as usize;
+    // unmake the last move done with make_move, panics if there isn't any
+    pub fn unmake_move(&mut self) {
+        let undo_info = self
+            .undo_stack
+            .pop()
+            .expect("unmake_move called without any move to unmake");
+        self.is_white_to_play = !self.is_white_to_play;
+        let color = self.is_white_to_play as usize;
+        let other_color = !self.is_white_to_play as usize;
+        let move_code = undo_info.move_code;
+
+        if let Some(side) = move_code.castling_side() {
+            let (
+                king_xor,
+                rook_xor,
+                king_from_index,
+                king_to_index,
+                rook_from_index,
+                rook_to_index,
+                color,
+                king_type,
+                rook_type,
+            ) = get_castling_infos(side);
+            self.pieces[king_type as usize] ^= king_xor;
+            self.pieces[rook_type as usize] ^= rook_xor;
+            self.players[color] ^= king_xor | rook_xor;
+            self.board ^= king_xor | rook_xor;
+            self.pieces_by_index[king_to_index] = TypePiece::Empty;
+            self.pieces_by_index[rook_to_index] = TypePiece::Empty;
+            self.pieces_by_index[king_from_index] = king_type;
+            self.pieces_by_index[rook_from_index] = rook_type;
+        } else {
+            let to_index = move_code.to_index();
+            let from_index = move_code.from_index();
+            let move_from_index = 1 << from_index;
+            let move_to_index = 1 << to_index;
+            let to_piece = self.pieces_by_index[to_index];
+            let from_piece = if move_code.kind() == MoveKind::Promotion {
+                if self.is_white_to_play {
+                    TypePiece::WhitePawn
+                } else {
+                    TypePiece::BlackPawn
+                }
+            } else {
+                to_piece
+            };
+
+            self.pieces[to_piece as usize] ^= move_to_index;
+            self.pieces[from_piece as usize] ^= move_from_index;
+            self.players[color] ^= move_to_index | move_from_index;
+            self.board ^= move_to_index | move_from_index;
+            self.pieces_by_index[to_index] = TypePiece::Empty;
+            self.pieces_by_index[from_index] = from_piece;
+
+            // takes
+            if undo_info.taken_piece != TypePiece::Empty {
+                let taken_mask = 1 << undo_info.taken_index;
+                self.pieces[undo_info.taken_piece as usize] |= taken_mask;
+                self.players[other_color] |= taken_mask;
+                self.board |= taken_mask;
+                self.pieces_by_index[undo_info.taken_index] = undo_info.taken_piece;
+            }
+        }
+
+        self.king_side_castle = undo_info.king_side_castle;
+        self.queen_side_castle = undo_info.queen_side_castle;
+        self.en_passant = undo_info.en_passant;
+        self.halfmove_clock = undo_info.halfmove_clock;
+        self.fullmove_number = undo_info.fullmove_number;
+        self.hash = undo_info.hash;
+    }
+
+    fn make_move_normal(&mut self, move_code: Move) {
+        let to_index = move_code.to_index();
+        let from_index = move_code.from_index();
         let color = self.is_white_to_play as usize;
         let other_color = !self.is_white_to_play as usize;
         let move_xor = (1 << to_index) | (1 << from_index);
         let move_from_index = 1 << from_index;
         let move_to_index = 1 << to_index;
 
+        // clocks
+        if self.pieces_by_index[to_index] == TypePiece::Empty
+            && self.pieces_by_index[from_index] != TypePiece::WhitePawn
+            && self.pieces_by_index[from_index] != TypePiece::BlackPawn
+        {
+            self.halfmove_clock += 1;
+        } else {
+            self.halfmove_clock = 0;
+        }
+        self.fullmove_number += !self.is_white_to_play as u16;
+
         // takes
         self.board |= move_to_index;
         self.pieces[self.pieces_by_index[to_index] as usize] &= !move_to_index;
         self.players[other_color] &= !move_to_index;
 
         self.pieces[self.pieces_by_index[from_index] as usize] ^= move_xor;
+        let piece_keys = &ZOBRIST_KEYS.pieces[self.pieces_by_index[from_index] as usize];
+        self.hash ^= piece_keys[from_index] ^ piece_keys[to_index];
         self.board ^= move_from_index;
         self.players[color] ^= move_xor;
 
@@ -90,37 +263,27 @@ impl ChessBoard {
         }
 
         // update-castling
-        self.king_side_castle[1] = !(self.king_side_castle[1] == false
-            || to_index == 63
-            || from_index == 63
-            || from_index == 60);
-        self.queen_side_castle[1] = !(self.queen_side_castle[1] == false
-            || to_index == 56
-            || from_index == 56
-            || from_index == 60);
-        self.king_side_castle[0] = !(self.king_side_castle[0] == false
-            || to_index == 7
-            || from_index == 7
-            || from_index == 4);
-        self.queen_side_castle[0] = !(self.queen_side_castle[0] == false
-            || to_index == 0
-            || from_index == 0
-            || from_index == 4);
+        self.king_side_castle[1] &= to_index != 63 && from_index != 63 && from_index != 60;
+        self.queen_side_castle[1] &= to_index != 56 && from_index != 56 && from_index != 60;
+        self.king_side_castle[0] &= to_index != 7 && from_index != 7 && from_index != 4;
+        self.queen_side_castle[0] &= to_index != 0 && from_index != 0 && from_index != 4;
 
         self.is_white_to_play = !self.is_white_to_play;
     }
 
-    fn make_move_promotion(&mut self, move_code: u16) {
-        let to_index = move_code & 0b111111;
-        let to_index = to_index as usize;
-        let from_index = (move_code >> 6) & 0b111111;
-        let from_index = from_index as usize;
+    fn make_move_promotion(&mut self, move_code: Move) {
+        let to_index = move_code.to_index();
+        let from_index = move_code.from_index();
         let color = self.is_white_to_play as usize;
         let other_color = !self.is_white_to_play as usize;
         let move_xor = (1 << to_index) | (1 << from_index);
         let move_from_index = 1 << from_index;
         let move_to_index = 1 << to_index;
-        let to_promotion = (move_code >> 12) & 0b11;
+        let to_promotion = move_code.promotion_piece().unwrap();
+
+        // clocks
+        self.halfmove_clock = 0;
+        self.fullmove_number += !self.is_white_to_play as u16;
 
         // takes
         self.board |= move_to_index;
@@ -135,22 +298,25 @@ impl ChessBoard {
         let promotion_piece_type =
             self.pieces_by_index[from_index] as usize - 4 + to_promotion as usize;
         self.pieces[promotion_piece_type] ^= move_to_index;
+        self.hash ^= ZOBRIST_KEYS.pieces[self.pieces_by_index[from_index] as usize][from_index]
+            ^ ZOBRIST_KEYS.pieces[promotion_piece_type][to_index];
 
+        self.pieces_by_index[to_index] = TYPE_PIECES[promotion_piece_type];
         self.pieces_by_index[from_index] = TypePiece::Empty;
 
         // update en-passant
         self.en_passant = 0;
 
         // update-castling
-        self.king_side_castle[1] = !(self.king_side_castle[1] == false || to_index == 63);
-        self.queen_side_castle[1] = !(self.queen_side_castle[1] == false || to_index == 56);
-        self.king_side_castle[0] = !(self.king_side_castle[0] == false || to_index == 7);
-        self.queen_side_castle[0] = !(self.queen_side_castle[0] == false || to_index == 0);
+        self.king_side_castle[1] &= to_index != 63;
+        self.queen_side_castle[1] &= to_index != 56;
+        self.king_side_castle[0] &= to_index != 7;
+        self.queen_side_castle[0] &= to_index != 0;
 
         self.is_white_to_play = !self.is_white_to_play;
     }
 
-    fn make_move_castling(&mut self, move_code: u16) {
+    fn make_move_castling(&mut self, move_code: Move) {
         let (
             king_xor,
             rook_xor,
@@ -161,55 +327,20 @@ impl ChessBoard {
             color,
             king_type,
             rook_type,
-        ) = match (move_code >> 12) & 0b11 {
-            0b00 => (
-                0b101000000000000000000000000000000000000000000000000000000000000,
-                0b1010000000000000000000000000000000000000000000000000000000000000,
-                60,
-                62,
-                63,
-                61,
-                1,
-                TypePiece::WhiteKing,
-                TypePiece::WhiteRook,
-            ),
-            0b01 => (
-                0b1010000000000000000000000000000000000000000000000000000000000,
-                0b100100000000000000000000000000000000000000000000000000000000,
-                60,
-                58,
-                56,
-                59,
-                1,
-                TypePiece::WhiteKing,
-                TypePiece::WhiteRook,
-            ),
-            0b10 => (
-                0b1010000,
-                0b10100000,
-                4,
-                6,
-                7,
-                5,
-                0,
-                TypePiece::BlackKing,
-                TypePiece::BlackRook,
-            ),
-            _ => (
-                0b10100,
-                0b1001,
-                4,
-                2,
-                0,
-                3,
-                0,
-                TypePiece::BlackKing,
-                TypePiece::BlackRook,
-            ),
-        };
+        ) = get_castling_infos(move_code.castling_side().unwrap());
+
+        // clocks
+        self.halfmove_clock += 1;
+        self.fullmove_number += !self.is_white_to_play as u16;
 
         self.pieces[king_type as usize] ^= king_xor;
         self.pieces[rook_type as usize] ^= rook_xor;
+        let king_keys = &ZOBRIST_KEYS.pieces[king_type as usize];
+        let rook_keys = &ZOBRIST_KEYS.pieces[rook_type as usize];
+        self.hash ^= king_keys[king_from_index]
+            ^ king_keys[king_to_index]
+            ^ rook_keys[rook_from_index]
+            ^ rook_keys[rook_to_index];
 
         self.players[color] ^= king_xor | rook_xor;
 
@@ -228,3 +359,93 @@ impl ChessBoard {
         self.is_white_to_play = !self.is_white_to_play;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, MainHashtables, generate_main_hashtables};
+    use crate::get_moves::MovesStruct;
+
+    fn assert_same_position(chessboard: &ChessBoard, expected: &ChessBoard, context: &str) {
+        assert_eq!(chessboard.board, expected.board, "{}", context);
+        assert_eq!(chessboard.players, expected.players, "{}", context);
+        assert_eq!(chessboard.pieces, expected.pieces, "{}", context);
+        assert_eq!(
+            chessboard.pieces_by_index, expected.pieces_by_index,
+            "{}",
+            context
+        );
+        assert_eq!(
+            chessboard.is_white_to_play, expected.is_white_to_play,
+            "{}",
+            context
+        );
+        assert_eq!(
+            chessboard.king_side_castle, expected.king_side_castle,
+            "{}",
+            context
+        );
+        assert_eq!(
+            chessboard.queen_side_castle, expected.queen_side_castle,
+            "{}",
+            context
+        );
+        assert_eq!(chessboard.en_passant, expected.en_passant, "{}", context);
+        assert_eq!(
+            chessboard.halfmove_clock, expected.halfmove_clock,
+            "{}",
+            context
+        );
+        assert_eq!(
+            chessboard.fullmove_number, expected.fullmove_number,
+            "{}",
+            context
+        );
+        assert_eq!(chessboard.hash, expected.hash, "{}", context);
+        assert_eq!(
+            chessboard.undo_stack.len(),
+            expected.undo_stack.len(),
+            "{}",
+            context
+        );
+    }
+
+    // makes and unmakes every legal move down to depth, checking the incremental hash on the way
+    fn check_round_trips(chessboard: &mut ChessBoard, ma: &MainHashtables, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves(ma, &mut moves);
+        for move_code in &moves.moves[..moves.move_number as usize] {
+            let before = chessboard.clone();
+            let context = format!("{} in {}", move_code, before.get_fen());
+            chessboard.make_move(*move_code);
+            assert_eq!(chessboard.hash, chessboard.compute_hash(), "{}", context);
+            check_round_trips(chessboard, ma, depth - 1);
+            chessboard.unmake_move();
+            assert_same_position(chessboard, &before, &context);
+        }
+    }
+
+    #[test]
+    fn unmake_move_restores_the_position() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let fens = [
+            // castling on both sides for both colors
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            // en passant
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3PpP2/8/PPP1P1PP/RNBQKBNR b KQkq f3 0 3",
+            // promotions, with and without captures
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+        for fen in fens {
+            let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+            check_round_trips(&mut chessboard, &ma, 2);
+            assert_eq!(chessboard.get_fen(), fen);
+        }
+    }
+}
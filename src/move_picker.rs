@@ -0,0 +1,315 @@
+use crate::binary_mask::MainHashtables;
+use crate::chess_move::{Move, MoveKind, PromotionPiece};
+use crate::evaluate::PIECE_VALUES;
+use crate::get_moves::{GenType, MovesStruct};
+use crate::{ChessBoard, TypePiece};
+
+// score of the quiet moves that caused a cutoff, by TypePiece and destination square
+pub type History = [[i32; 64]; 12];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    Evasions,
+    TtMove,
+    Captures,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// gives the moves of a position one by one, the most promising first
+// the quiet moves are only generated once every capture has been tried, the captures losing
+// material according to see are tried last
+// the tt move and the killers come from other positions, they are checked with is_legal_move and
+// skipped when they are generated again
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Move,
+    killers: [Move; 2],
+    killer_index: usize,
+    // the quiescence search only looks at the captures not losing material
+    with_quiets: bool,
+    // moves already given are replaced by Move::NULL
+    captures: Option<MovesStruct>,
+    capture_scores: [i32; 255],
+    quiets: Option<MovesStruct>,
+    quiet_scores: [i32; 255],
+    bad_captures: Vec<Move>,
+    bad_capture_index: usize,
+}
+
+impl ChessBoard {
+    pub fn is_capture(&self, move_code: Move) -> bool {
+        self.pieces_by_index[move_code.to_index()] != TypePiece::Empty
+            || move_code.kind() == MoveKind::EnPassant
+    }
+
+    // the moves generated by GenType::Quiets, the promotions are tried with the captures
+    pub fn is_quiet(&self, move_code: Move) -> bool {
+        !self.is_capture(move_code) && move_code.kind() != MoveKind::Promotion
+    }
+
+    // most valuable victim first, then least valuable attacker
+    fn get_mvv_lva_score(&self, move_code: Move) -> i32 {
+        let attacker = self.pieces_by_index[move_code.from_index()] as usize % 6;
+        let victim = match move_code.kind() {
+            MoveKind::EnPassant => TypePiece::WhitePawn as usize,
+            _ => self.pieces_by_index[move_code.to_index()] as usize % 6,
+        };
+        let mut score = PIECE_VALUES[victim] * 10 - PIECE_VALUES[attacker];
+        if move_code.promotion_piece() == Some(PromotionPiece::Queen) {
+            score += PIECE_VALUES[TypePiece::WhiteQueen as usize] * 10;
+        }
+        score
+    }
+}
+
+// index of the best scored move left, the moves already given are skipped
+fn pick_best(moves: &mut MovesStruct, scores: &[i32; 255]) -> Option<Move> {
+    let mut best_index = None;
+    for i in 0..moves.move_number as usize {
+        if moves.moves[i] != Move::NULL
+            && best_index.is_none_or(|best_index: usize| scores[i] > scores[best_index])
+        {
+            best_index = Some(i);
+        }
+    }
+    best_index.map(|i| std::mem::take(&mut moves.moves[i]))
+}
+
+impl MovePicker {
+    pub fn new(tt_move: Move, killers: [Move; 2]) -> MovePicker {
+        MovePicker {
+            stage: Stage::TtMove,
+            tt_move,
+            killers,
+            killer_index: 0,
+            with_quiets: true,
+            captures: None,
+            capture_scores: [0; 255],
+            quiets: None,
+            quiet_scores: [0; 255],
+            bad_captures: Vec::new(),
+            bad_capture_index: 0,
+        }
+    }
+
+    pub fn new_captures() -> MovePicker {
+        MovePicker {
+            stage: Stage::Captures,
+            with_quiets: false,
+            ..MovePicker::new(Move::NULL, [Move::NULL; 2])
+        }
+    }
+
+    // every legal move of a position in check, for the quiescence search which can't stand pat
+    pub fn new_evasions() -> MovePicker {
+        MovePicker {
+            stage: Stage::Evasions,
+            ..MovePicker::new(Move::NULL, [Move::NULL; 2])
+        }
+    }
+
+    fn generate_captures(&mut self, chessboard: &ChessBoard, ma: &MainHashtables) {
+        if self.captures.is_some() {
+            return;
+        }
+        let mut captures = MovesStruct::init();
+        chessboard.get_moves_by_type(ma, GenType::Captures, &mut captures);
+        for i in 0..captures.move_number as usize {
+            self.capture_scores[i] = chessboard.get_mvv_lva_score(captures.moves[i]);
+        }
+        self.captures = Some(captures);
+    }
+
+    // the evasions are kept with the captures, the captures first then the quiets by history
+    fn generate_evasions(
+        &mut self,
+        chessboard: &ChessBoard,
+        ma: &MainHashtables,
+        history: &History,
+    ) {
+        if self.captures.is_some() {
+            return;
+        }
+        let mut evasions = MovesStruct::init();
+        chessboard.get_moves_by_type(ma, GenType::All, &mut evasions);
+        for i in 0..evasions.move_number as usize {
+            let move_code = evasions.moves[i];
+            self.capture_scores[i] = if chessboard.is_quiet(move_code) {
+                let piece = chessboard.pieces_by_index[move_code.from_index()] as usize;
+                history[piece][move_code.to_index()]
+            } else {
+                (1 << 24) + chessboard.get_mvv_lva_score(move_code)
+            };
+        }
+        self.captures = Some(evasions);
+    }
+
+    fn generate_quiets(&mut self, chessboard: &ChessBoard, ma: &MainHashtables, history: &History) {
+        if self.quiets.is_some() {
+            return;
+        }
+        let mut quiets = MovesStruct::init();
+        chessboard.get_moves_by_type(ma, GenType::Quiets, &mut quiets);
+        for i in 0..quiets.move_number as usize {
+            let move_code = quiets.moves[i];
+            let piece = chessboard.pieces_by_index[move_code.from_index()] as usize;
+            self.quiet_scores[i] = history[piece][move_code.to_index()];
+        }
+        self.quiets = Some(quiets);
+    }
+
+    pub fn next(
+        &mut self,
+        chessboard: &ChessBoard,
+        ma: &MainHashtables,
+        history: &History,
+    ) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::Evasions => {
+                    self.generate_evasions(chessboard, ma, history);
+                    let evasions = self.captures.as_mut().unwrap();
+                    if let Some(move_code) = pick_best(evasions, &self.capture_scores) {
+                        return Some(move_code);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::TtMove => {
+                    self.stage = Stage::Captures;
+                    // the tt move could come from another position with the same hash
+                    if chessboard.is_legal_move(ma, self.tt_move) {
+                        return Some(self.tt_move);
+                    }
+                    self.tt_move = Move::NULL;
+                }
+                Stage::Captures => {
+                    self.generate_captures(chessboard, ma);
+                    let captures = self.captures.as_mut().unwrap();
+                    if let Some(move_code) = pick_best(captures, &self.capture_scores) {
+                        if move_code == self.tt_move {
+                            continue;
+                        }
+                        if chessboard.see(ma, move_code) < 0 {
+                            self.bad_captures.push(move_code);
+                            continue;
+                        }
+                        return Some(move_code);
+                    }
+                    self.stage = if self.with_quiets {
+                        Stage::Killers
+                    } else {
+                        Stage::Done
+                    };
+                }
+                Stage::Killers => {
+                    if self.killer_index == self.killers.len() {
+                        self.stage = Stage::Quiets;
+                        continue;
+                    }
+                    let killer = self.killers[self.killer_index];
+                    self.killer_index += 1;
+                    // a killer is a quiet move of another position at the same ply
+                    if killer != self.tt_move
+                        && chessboard.is_quiet(killer)
+                        && chessboard.is_legal_move(ma, killer)
+                    {
+                        return Some(killer);
+                    }
+                    // not given so it isn't skipped among the quiets
+                    self.killers[self.killer_index - 1] = Move::NULL;
+                }
+                Stage::Quiets => {
+                    self.generate_quiets(chessboard, ma, history);
+                    let quiets = self.quiets.as_mut().unwrap();
+                    if let Some(move_code) = pick_best(quiets, &self.quiet_scores) {
+                        if move_code == self.tt_move || self.killers.contains(&move_code) {
+                            continue;
+                        }
+                        return Some(move_code);
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => {
+                    if let Some(move_code) = self.bad_captures.get(self.bad_capture_index) {
+                        self.bad_capture_index += 1;
+                        return Some(*move_code);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::parse_uci_move;
+
+    #[test]
+    fn every_legal_move_is_picked_once() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let chessboard = ChessBoard::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves(&ma, &mut moves);
+        let mut expected = moves.moves[..moves.move_number as usize].to_vec();
+        expected.sort_by_key(|move_code| move_code.to_u16());
+
+        let tt_move = parse_uci_move(&chessboard, &ma, "e1g1").unwrap();
+        let killer = parse_uci_move(&chessboard, &ma, "a2a3").unwrap();
+        // e2e4 isn't legal here, the killer slot holding it is skipped
+        let illegal = Move::new(52, 36);
+        let mut move_picker = MovePicker::new(tt_move, [illegal, killer]);
+        let history = [[0; 64]; 12];
+        let mut picked = Vec::new();
+        while let Some(move_code) = move_picker.next(&chessboard, &ma, &history) {
+            picked.push(move_code);
+        }
+        assert_eq!(picked[0], tt_move);
+        // the most valuable victim, the bishop on a6
+        assert_eq!(picked[1], parse_uci_move(&chessboard, &ma, "e2a6").unwrap());
+        let first_quiet = picked
+            .iter()
+            .position(|move_code| chessboard.is_quiet(*move_code) && *move_code != tt_move)
+            .unwrap();
+        assert_eq!(picked[first_quiet], killer);
+        picked.sort_by_key(|move_code| move_code.to_u16());
+        assert_eq!(picked, expected);
+    }
+
+    #[test]
+    fn promotions_are_not_killers() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let chessboard = ChessBoard::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = parse_uci_move(&chessboard, &ma, "b7b8n").unwrap();
+        assert!(!chessboard.is_quiet(promotion));
+
+        // the promotions are given with the captures, before the quiet moves of the king
+        let mut move_picker = MovePicker::new(Move::NULL, [promotion, Move::NULL]);
+        let history = [[0; 64]; 12];
+        let mut picked = Vec::new();
+        while let Some(move_code) = move_picker.next(&chessboard, &ma, &history) {
+            picked.push(move_code);
+        }
+        assert_eq!(picked.len(), 9);
+        assert!(picked[..4].contains(&promotion));
+        assert!(
+            picked[..4]
+                .iter()
+                .all(|move_code| move_code.kind() == MoveKind::Promotion)
+        );
+        assert!(
+            picked[4..]
+                .iter()
+                .all(|move_code| chessboard.is_quiet(*move_code))
+        );
+    }
+}
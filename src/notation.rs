@@ -1,6 +1,104 @@
-use crate::ChessBoard;
+use std::fmt;
 
-fn index_to_square(index: usize) -> String {
+use crate::binary_mask::MainHashtables;
+use crate::chess_move::{Move, PROMOTION_PIECES};
+use crate::get_moves::MovesStruct;
+use crate::{ChessBoard, TypePiece};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, PartialEq)]
+pub enum FenError {
+    WrongFieldNumber(usize),
+    WrongRankNumber(usize),
+    // rank as written in the notation (8 for the first rank of the fen), length found
+    BadRankLength(usize, usize),
+    // rank as written in the notation, digit found
+    BadEmptySquareCount(usize, char),
+    // rank as written in the notation
+    ConsecutiveDigits(usize),
+    UnknownPiece(char),
+    // is_white, number of kings found
+    WrongKingNumber(bool, u32),
+    PawnOnBackRank,
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldNumber(number) => {
+                write!(f, "expected 4 to 6 fields, found {}", number)
+            }
+            FenError::WrongRankNumber(number) => write!(f, "expected 8 ranks, found {}", number),
+            FenError::BadRankLength(rank, length) => {
+                write!(f, "rank {} describes {} squares instead of 8", rank, length)
+            }
+            FenError::BadEmptySquareCount(rank, digit) => write!(
+                f,
+                "rank {} skips '{}' squares, expected a digit from 1 to 8",
+                rank, digit
+            ),
+            FenError::ConsecutiveDigits(rank) => {
+                write!(f, "rank {} has two digits in a row", rank)
+            }
+            FenError::UnknownPiece(letter) => write!(f, "unknown piece letter '{}'", letter),
+            FenError::WrongKingNumber(is_white, number) => write!(
+                f,
+                "expected exactly one {} king, found {}",
+                if *is_white { "white" } else { "black" },
+                number
+            ),
+            FenError::PawnOnBackRank => write!(f, "pawns can't be on the first or last rank"),
+            FenError::InvalidSideToMove(field) => {
+                write!(f, "invalid side to move '{}', expected 'w' or 'b'", field)
+            }
+            FenError::InvalidCastlingRights(field) => {
+                write!(f, "invalid castling rights '{}'", field)
+            }
+            FenError::InvalidEnPassant(field) => write!(f, "invalid en-passant square '{}'", field),
+            FenError::InvalidHalfmoveClock(field) => {
+                write!(f, "invalid halfmove clock '{}'", field)
+            }
+            FenError::InvalidFullmoveNumber(field) => {
+                write!(f, "invalid fullmove number '{}'", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+#[derive(Debug, PartialEq)]
+pub enum UciMoveError {
+    InvalidNotation(String),
+    MissingPromotion(String),
+    IllegalMove(String),
+}
+
+impl fmt::Display for UciMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciMoveError::InvalidNotation(notation) => {
+                write!(f, "'{}' isn't a move in uci notation", notation)
+            }
+            UciMoveError::MissingPromotion(notation) => {
+                write!(f, "'{}' is a promotion but no piece is given", notation)
+            }
+            UciMoveError::IllegalMove(notation) => {
+                write!(f, "'{}' is illegal in this position", notation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UciMoveError {}
+
+pub fn index_to_square(index: usize) -> String {
     let mut result = String::new();
     let x = index % 8;
     let y = index / 8;
@@ -11,13 +109,232 @@ fn index_to_square(index: usize) -> String {
     result
 }
 
-pub fn get_notation_from_move(move_code: u16) -> String {
-    let to_index = move_code & 0b111111;
-    let from_index = (move_code >> 6) & 0b111111;
-    index_to_square(from_index as usize) + &index_to_square(to_index as usize)
+// "e4" -> 36, the inverse of index_to_square
+pub fn square_to_index(square: &str) -> Option<usize> {
+    let mut chars = square.chars();
+    let x = "abcdefgh".find(chars.next()?)?;
+    let y = "87654321".find(chars.next()?)?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(y * 8 + x)
+}
+
+pub fn get_notation_from_move(move_code: Move) -> String {
+    move_code.to_string()
+}
+
+// "e2e4", "e7e8q", "e1g1" -> the legal move of the position with the matching kind
+pub fn parse_uci_move(
+    chessboard: &ChessBoard,
+    ma: &MainHashtables,
+    notation: &str,
+) -> Result<Move, UciMoveError> {
+    let invalid_notation = || UciMoveError::InvalidNotation(notation.to_string());
+    if !notation.is_ascii() || notation.len() < 4 || notation.len() > 5 {
+        return Err(invalid_notation());
+    }
+    let from_index = square_to_index(&notation[0..2]).ok_or_else(invalid_notation)?;
+    let to_index = square_to_index(&notation[2..4]).ok_or_else(invalid_notation)?;
+    let promotion_piece = match notation[4..].chars().next() {
+        None => None,
+        Some(letter) => match "qrbn".find(letter) {
+            Some(i) => Some(PROMOTION_PIECES[i]),
+            None => return Err(invalid_notation()),
+        },
+    };
+
+    let mut moves = MovesStruct::init();
+    chessboard.get_moves(ma, &mut moves);
+    let mut same_squares = moves.moves[..moves.move_number as usize]
+        .iter()
+        .filter(|move_code| {
+            move_code.from_index() == from_index && move_code.to_index() == to_index
+        })
+        .peekable();
+    if same_squares.peek().is_none() {
+        return Err(UciMoveError::IllegalMove(notation.to_string()));
+    }
+    let mut is_promotion = false;
+    for move_code in same_squares {
+        if move_code.promotion_piece() == promotion_piece {
+            return Ok(*move_code);
+        }
+        is_promotion |= move_code.promotion_piece().is_some();
+    }
+    if is_promotion && promotion_piece.is_none() {
+        return Err(UciMoveError::MissingPromotion(notation.to_string()));
+    }
+    Err(UciMoveError::IllegalMove(notation.to_string()))
 }
 
 impl ChessBoard {
+    pub fn from_fen(fen: &str) -> Result<ChessBoard, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(FenError::WrongFieldNumber(fields.len()));
+        }
+
+        // board
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongRankNumber(ranks.len()));
+        }
+        let mut pieces_by_index = [TypePiece::Empty; 64];
+        for (y, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            let mut is_previous_digit = false;
+            for letter in rank.chars() {
+                if letter.is_ascii_digit() {
+                    if !('1'..='8').contains(&letter) {
+                        return Err(FenError::BadEmptySquareCount(8 - y, letter));
+                    }
+                    if is_previous_digit {
+                        return Err(FenError::ConsecutiveDigits(8 - y));
+                    }
+                    is_previous_digit = true;
+                    x += letter as usize - '0' as usize;
+                    continue;
+                }
+                is_previous_digit = false;
+                let type_piece = match letter {
+                    'K' => TypePiece::WhiteKing,
+                    'Q' => TypePiece::WhiteQueen,
+                    'R' => TypePiece::WhiteRook,
+                    'B' => TypePiece::WhiteBishop,
+                    'N' => TypePiece::WhiteKnight,
+                    'P' => TypePiece::WhitePawn,
+                    'k' => TypePiece::BlackKing,
+                    'q' => TypePiece::BlackQueen,
+                    'r' => TypePiece::BlackRook,
+                    'b' => TypePiece::BlackBishop,
+                    'n' => TypePiece::BlackKnight,
+                    'p' => TypePiece::BlackPawn,
+                    _ => return Err(FenError::UnknownPiece(letter)),
+                };
+                if x < 8 {
+                    pieces_by_index[y * 8 + x] = type_piece;
+                }
+                x += 1;
+            }
+            if x != 8 {
+                return Err(FenError::BadRankLength(8 - y, x));
+            }
+        }
+
+        let mut pieces = [0u64; 13];
+        for (i, type_piece) in pieces_by_index.iter().enumerate() {
+            if *type_piece != TypePiece::Empty {
+                pieces[*type_piece as usize] |= 1 << i;
+            }
+        }
+        pieces[TypePiece::Empty as usize] = 0;
+        let white_pieces = pieces[..6].iter().fold(0, |a, b| a | b);
+        let black_pieces = pieces[6..12].iter().fold(0, |a, b| a | b);
+
+        for (is_white, king) in [
+            (true, pieces[TypePiece::WhiteKing as usize]),
+            (false, pieces[TypePiece::BlackKing as usize]),
+        ] {
+            if king.count_ones() != 1 {
+                return Err(FenError::WrongKingNumber(is_white, king.count_ones()));
+            }
+        }
+        let back_ranks = 0xff000000000000ff;
+        if (pieces[TypePiece::WhitePawn as usize] | pieces[TypePiece::BlackPawn as usize])
+            & back_ranks
+            != 0
+        {
+            return Err(FenError::PawnOnBackRank);
+        }
+
+        // player turn
+        let is_white_to_play = match fields[1] {
+            "w" => true,
+            "b" => false,
+            _ => return Err(FenError::InvalidSideToMove(fields[1].to_string())),
+        };
+
+        // castles
+        // a right is only valid if the king and the rook are still on their starting squares
+        let mut king_side_castle = [false, false];
+        let mut queen_side_castle = [false, false];
+        if fields[2] != "-" {
+            for letter in fields[2].chars() {
+                let (castle, color, king_index, rook_index) = match letter {
+                    'K' => (&mut king_side_castle, 1, 60, 63),
+                    'Q' => (&mut queen_side_castle, 1, 60, 56),
+                    'k' => (&mut king_side_castle, 0, 4, 7),
+                    'q' => (&mut queen_side_castle, 0, 4, 0),
+                    _ => return Err(FenError::InvalidCastlingRights(fields[2].to_string())),
+                };
+                let offset = if color == 1 { 0 } else { 6 };
+                if castle[color]
+                    || pieces_by_index[king_index] as usize
+                        != TypePiece::WhiteKing as usize + offset
+                    || pieces_by_index[rook_index] as usize
+                        != TypePiece::WhiteRook as usize + offset
+                {
+                    return Err(FenError::InvalidCastlingRights(fields[2].to_string()));
+                }
+                castle[color] = true;
+            }
+        }
+
+        // en-passant
+        // the square must be behind a pawn that could just have been pushed two squares
+        let mut en_passant = 0;
+        if fields[3] != "-" {
+            let (expected_rank, pawn_offset, type_pawn) = if is_white_to_play {
+                (2, 8, TypePiece::BlackPawn)
+            } else {
+                (5, -8, TypePiece::WhitePawn)
+            };
+            match square_to_index(fields[3]) {
+                Some(index)
+                    if index / 8 == expected_rank
+                        && pieces_by_index[index] == TypePiece::Empty
+                        && pieces_by_index[(index as i32 + pawn_offset) as usize] == type_pawn =>
+                {
+                    en_passant = 1 << index;
+                }
+                _ => return Err(FenError::InvalidEnPassant(fields[3].to_string())),
+            }
+        }
+
+        // clocks, optional as a lot of tools only write the first 4 fields
+        let halfmove_clock = match fields.get(4) {
+            Some(field) => field
+                .parse::<u16>()
+                .map_err(|_| FenError::InvalidHalfmoveClock(field.to_string()))?,
+            None => 0,
+        };
+        let fullmove_number = match fields.get(5) {
+            Some(field) => match field.parse::<u16>() {
+                Ok(number) if number >= 1 => number,
+                _ => return Err(FenError::InvalidFullmoveNumber(field.to_string())),
+            },
+            None => 1,
+        };
+
+        let mut chessboard = ChessBoard {
+            board: white_pieces | black_pieces,
+            players: [black_pieces, white_pieces],
+            pieces,
+            pieces_by_index,
+            is_white_to_play,
+            king_side_castle,
+            queen_side_castle,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            undo_stack: Vec::new(),
+            hash: 0,
+        };
+        chessboard.hash = chessboard.compute_hash();
+        Ok(chessboard)
+    }
+
     pub fn get_fen(&self) -> String {
         // board
         let letters = [
@@ -26,8 +343,8 @@ impl ChessBoard {
         let mut fen_board = String::new();
         for i in 0..64 {
             fen_board.push_str(letters[self.pieces_by_index[i] as usize]);
-            if i % 8 == 7 {
-                fen_board.push_str("/");
+            if i % 8 == 7 && i != 63 {
+                fen_board.push('/');
             }
         }
         fen_board = fen_board
@@ -45,18 +362,268 @@ impl ChessBoard {
         // castles
         let mut fen_castles = String::new();
         if self.king_side_castle[1] {
-            fen_castles.push_str("K");
+            fen_castles.push('K');
         }
         if self.queen_side_castle[1] {
-            fen_castles.push_str("Q");
+            fen_castles.push('Q');
         }
         if self.king_side_castle[0] {
-            fen_castles.push_str("k");
+            fen_castles.push('k');
         }
         if self.queen_side_castle[0] {
-            fen_castles.push_str("q");
+            fen_castles.push('q');
         }
+        if fen_castles.is_empty() {
+            fen_castles.push('-');
+        }
+
+        // en-passant
+        let fen_en_passant = if self.en_passant == 0 {
+            String::from("-")
+        } else {
+            index_to_square(self.en_passant.trailing_zeros() as usize)
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            fen_board,
+            fen_player_turn,
+            fen_castles,
+            fen_en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::chess_move::{CastlingSide, PromotionPiece};
+
+    #[test]
+    fn fen_is_read() {
+        let chessboard =
+            ChessBoard::from_fen("rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 2")
+                .unwrap();
+        assert_eq!(chessboard.board.count_ones(), 32);
+        assert_eq!(chessboard.players[1].count_ones(), 16);
+        assert_eq!(chessboard.pieces_by_index[4], TypePiece::BlackKing);
+        assert_eq!(chessboard.pieces_by_index[60], TypePiece::WhiteKing);
+        assert_eq!(chessboard.pieces_by_index[35], TypePiece::WhitePawn);
+        assert_eq!(chessboard.pieces_by_index[36], TypePiece::BlackPawn);
+        assert_eq!(chessboard.pieces_by_index[52], TypePiece::WhitePawn);
+        assert_eq!(chessboard.pieces_by_index[51], TypePiece::Empty);
+        assert!(!chessboard.is_white_to_play);
+        assert_eq!(chessboard.king_side_castle, [false, true]);
+        assert_eq!(chessboard.queen_side_castle, [true, false]);
+        assert_eq!(chessboard.en_passant, 1 << 43);
+    }
+
+    #[test]
+    fn fen_is_written_back_unchanged() {
+        for fen in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 2",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 12 40",
+        ] {
+            assert_eq!(ChessBoard::from_fen(fen).unwrap().get_fen(), fen);
+        }
+    }
 
-        fen_board + " " + &fen_player_turn + " " + &fen_castles
+    #[test]
+    fn invalid_fens_are_rejected() {
+        let errors = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+                FenError::WrongFieldNumber(1),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::WrongRankNumber(7),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/7/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::BadRankLength(4, 7),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/44P/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+                FenError::ConsecutiveDigits(4),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/08/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::BadEmptySquareCount(4, '0'),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/9/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::BadEmptySquareCount(4, '9'),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4X3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::UnknownPiece('X'),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4K3/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+                FenError::WrongKingNumber(true, 2),
+            ),
+            (
+                "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+                FenError::WrongKingNumber(false, 0),
+            ),
+            (
+                "rnbqkbnP/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR w - - 0 1",
+                FenError::PawnOnBackRank,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+                FenError::InvalidCastlingRights("KQkx".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+                FenError::InvalidCastlingRights("KQkq".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+                FenError::InvalidEnPassant("e3".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+                FenError::InvalidEnPassant("e9".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+                FenError::InvalidHalfmoveClock("x".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_string()),
+            ),
+        ];
+        for (fen, error) in errors {
+            assert_eq!(ChessBoard::from_fen(fen).err(), Some(error), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn uci_moves_are_parsed() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let moves = [
+            (STARTING_FEN, "e2e4", Move::new(52, 36)),
+            (
+                "8/4P3/8/8/8/8/k7/4K3 w - - 0 1",
+                "e7e8q",
+                Move::new_promotion(12, 4, PromotionPiece::Queen),
+            ),
+            (
+                "8/4P3/8/8/8/8/k7/4K3 w - - 0 1",
+                "e7e8n",
+                Move::new_promotion(12, 4, PromotionPiece::Knight),
+            ),
+            (
+                "7k/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                "e5d6",
+                Move::new_en_passant(28, 19),
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                "e1g1",
+                Move::new_castling(CastlingSide::WhiteKingSide),
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                "e8c8",
+                Move::new_castling(CastlingSide::BlackQueenSide),
+            ),
+        ];
+        for (fen, notation, move_code) in moves {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(
+                parse_uci_move(&chessboard, &ma, notation),
+                Ok(move_code),
+                "{} {}",
+                fen,
+                notation
+            );
+            assert_eq!(move_code.to_string(), notation);
+        }
+    }
+
+    #[test]
+    fn invalid_uci_moves_are_rejected() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let errors = [
+            (
+                STARTING_FEN,
+                "e2e",
+                UciMoveError::InvalidNotation("e2e".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e2e4qq",
+                UciMoveError::InvalidNotation("e2e4qq".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "i2e4",
+                UciMoveError::InvalidNotation("i2e4".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e2e9",
+                UciMoveError::InvalidNotation("e2e9".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e2e4k",
+                UciMoveError::InvalidNotation("e2e4k".to_string()),
+            ),
+            (
+                "8/4P3/8/8/8/8/k7/4K3 w - - 0 1",
+                "e7e8",
+                UciMoveError::MissingPromotion("e7e8".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e2e5",
+                UciMoveError::IllegalMove("e2e5".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e4e5",
+                UciMoveError::IllegalMove("e4e5".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e7e5",
+                UciMoveError::IllegalMove("e7e5".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e2e4q",
+                UciMoveError::IllegalMove("e2e4q".to_string()),
+            ),
+            (
+                STARTING_FEN,
+                "e1g1",
+                UciMoveError::IllegalMove("e1g1".to_string()),
+            ),
+        ];
+        for (fen, notation, error) in errors {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(
+                parse_uci_move(&chessboard, &ma, notation),
+                Err(error),
+                "{} {}",
+                fen,
+                notation
+            );
+        }
     }
 }
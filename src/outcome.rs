@@ -0,0 +1,165 @@
+use crate::binary_mask::MainHashtables;
+use crate::get_moves::MovesStruct;
+use crate::{ChessBoard, TypePiece};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Ongoing,
+    Checkmate { is_white_winner: bool },
+    Stalemate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+}
+
+impl Outcome {
+    pub fn is_draw(self) -> bool {
+        !matches!(self, Outcome::Ongoing | Outcome::Checkmate { .. })
+    }
+}
+
+// a8 is a light square
+const LIGHT_SQUARES: u64 = 0xaa55aa55aa55aa55;
+
+impl ChessBoard {
+    // number of times the current position already happened before, only positions since the
+    // last capture or pawn move with the same player to play can be the same
+    pub fn count_repetitions(&self) -> usize {
+        self.undo_stack
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|undo_info| undo_info.hash == self.hash)
+            .count()
+    }
+
+    // neither player can checkmate whatever the moves played
+    pub fn is_insufficient_material(&self) -> bool {
+        let pieces = |type_piece: TypePiece| self.pieces[type_piece as usize];
+        let major_pieces_and_pawns = pieces(TypePiece::WhiteQueen)
+            | pieces(TypePiece::WhiteRook)
+            | pieces(TypePiece::WhitePawn)
+            | pieces(TypePiece::BlackQueen)
+            | pieces(TypePiece::BlackRook)
+            | pieces(TypePiece::BlackPawn);
+        if major_pieces_and_pawns != 0 {
+            return false;
+        }
+        let knights = pieces(TypePiece::WhiteKnight) | pieces(TypePiece::BlackKnight);
+        let bishops = pieces(TypePiece::WhiteBishop) | pieces(TypePiece::BlackBishop);
+        // a lone minor piece, or only bishops all moving on the same color of squares
+        (knights | bishops).count_ones() <= 1
+            || knights == 0 && (bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0)
+    }
+
+    pub fn get_outcome(&self, ma: &MainHashtables) -> Outcome {
+        let mut moves = MovesStruct::init();
+        self.get_moves(ma, &mut moves);
+        if moves.move_number == 0 {
+            if self.is_in_check(ma) {
+                return Outcome::Checkmate {
+                    is_white_winner: !self.is_white_to_play,
+                };
+            }
+            return Outcome::Stalemate;
+        }
+        if self.halfmove_clock >= 100 {
+            return Outcome::FiftyMoveRule;
+        }
+        if self.is_insufficient_material() {
+            return Outcome::InsufficientMaterial;
+        }
+        if self.count_repetitions() >= 2 {
+            return Outcome::ThreefoldRepetition;
+        }
+        Outcome::Ongoing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::chess_move::Move;
+    use crate::notation::STARTING_FEN;
+
+    #[test]
+    fn game_endings() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let positions = [
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+                Outcome::Checkmate {
+                    is_white_winner: false,
+                },
+            ),
+            (
+                "3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+                Outcome::Checkmate {
+                    is_white_winner: true,
+                },
+            ),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Outcome::Stalemate),
+            ("4k3/8/8/8/8/8/4R3/4K3 b - - 99 80", Outcome::Ongoing),
+            ("4k3/8/8/8/8/8/4R3/4K3 b - - 100 80", Outcome::FiftyMoveRule),
+            (STARTING_FEN, Outcome::Ongoing),
+        ];
+        for (fen, outcome) in positions {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(chessboard.get_outcome(&ma), outcome, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let mut chessboard = ChessBoard::from_fen(STARTING_FEN).unwrap();
+        for repetition in 1..=2 {
+            // Nf3 Nf6 Ng1 Ng8
+            for (from_index, to_index) in [(62, 45), (6, 21), (45, 62), (21, 6)] {
+                chessboard.make_move(Move::new(from_index, to_index));
+            }
+            assert_eq!(chessboard.count_repetitions(), repetition);
+        }
+        assert_eq!(chessboard.get_outcome(&ma), Outcome::ThreefoldRepetition);
+        chessboard.unmake_move();
+        assert_eq!(chessboard.get_outcome(&ma), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn repetition_after_a_double_pawn_push() {
+        let mut chessboard = ChessBoard::from_fen(STARTING_FEN).unwrap();
+        // e4, then Nf6 Nf3 Ng8 Ng1 comes back to the position after e4 without the en-passant
+        // square, which no black pawn could use
+        for (from_index, to_index) in [(52, 36), (6, 21), (62, 45), (21, 6), (45, 62)] {
+            chessboard.make_move(Move::new(from_index, to_index));
+        }
+        assert_eq!(chessboard.count_repetitions(), 1);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let positions = [
+            ("8/8/4k3/8/8/4K3/8/8 w - - 0 1", true),
+            ("8/8/4k3/8/8/4K3/8/2B5 w - - 0 1", true),
+            ("8/8/4k3/8/8/4K3/8/6n1 w - - 0 1", true),
+            // c1 and f8 are both dark squares
+            ("5b2/8/4k3/8/8/4K3/8/2B5 w - - 0 1", true),
+            ("2b5/8/4k3/8/8/4K3/8/2B5 w - - 0 1", false),
+            ("8/8/4k3/8/8/4K3/8/2B1N3 w - - 0 1", false),
+            ("8/8/4k3/8/8/4K3/8/1N4N1 w - - 0 1", false),
+            ("8/8/4k3/8/8/4K3/4P3/8 w - - 0 1", false),
+        ];
+        for (fen, is_insufficient) in positions {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(
+                chessboard.is_insufficient_material(),
+                is_insufficient,
+                "{}",
+                fen
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::ChessBoard;
+use crate::binary_mask::MainHashtables;
+use crate::get_moves::MovesStruct;
+use crate::notation::get_notation_from_move;
+
+// number of leaf nodes at the given depth, used to check the move generator against known values
+pub fn perft(chessboard: &mut ChessBoard, ma: &MainHashtables, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut moves = MovesStruct::init();
+    chessboard.get_moves(ma, &mut moves);
+    if depth == 1 {
+        // the moves are legal, no need to play them
+        return moves.move_number as u64;
+    }
+
+    let mut nodes = 0;
+    for move_code in &moves.moves[..moves.move_number as usize] {
+        chessboard.make_move(*move_code);
+        nodes += perft(chessboard, ma, depth - 1);
+        chessboard.unmake_move();
+    }
+    nodes
+}
+
+// perft with the number of leaf nodes printed for each move of the root position, the total is
+// returned
+pub fn divide(chessboard: &mut ChessBoard, ma: &MainHashtables, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut moves = MovesStruct::init();
+    chessboard.get_moves(ma, &mut moves);
+
+    let mut nodes = 0;
+    for move_code in &moves.moves[..moves.move_number as usize] {
+        chessboard.make_move(*move_code);
+        let move_nodes = perft(chessboard, ma, depth - 1);
+        chessboard.unmake_move();
+        println!("{}: {}", get_notation_from_move(*move_code), move_nodes);
+        nodes += move_nodes;
+    }
+    println!();
+    println!("Nodes searched: {}", nodes);
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::STARTING_FEN;
+
+    fn get_main_hashtables() -> &'static MainHashtables {
+        static MAIN_HASHTABLES: OnceLock<MainHashtables> = OnceLock::new();
+        MAIN_HASHTABLES.get_or_init(|| generate_main_hashtables(MagicSource::Precomputed))
+    }
+
+    fn assert_perft(fen: &str, expected_nodes: &[u64]) {
+        let ma = get_main_hashtables();
+        let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+        for (depth, expected) in expected_nodes.iter().enumerate() {
+            let nodes = perft(&mut chessboard, ma, depth as u8 + 1);
+            assert_eq!(nodes, *expected, "{} at depth {}", fen, depth + 1);
+        }
+        // perft must leave the board as it found it
+        assert_eq!(
+            chessboard.get_fen(),
+            ChessBoard::from_fen(fen).unwrap().get_fen()
+        );
+    }
+
+    #[test]
+    fn perft_initial_position() {
+        assert_perft(STARTING_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        );
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238, 674624],
+        );
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467, 422333],
+        );
+    }
+
+    #[test]
+    fn perft_position_4_mirrored() {
+        assert_perft(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9467, 422333],
+        );
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        );
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        );
+    }
+}
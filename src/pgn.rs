@@ -0,0 +1,567 @@
+use std::fmt;
+
+use crate::ChessBoard;
+use crate::binary_mask::MainHashtables;
+use crate::chess_move::Move;
+use crate::notation::{FenError, STARTING_FEN};
+use crate::san::SanError;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+// move suffix annotations and the nag they stand for
+const SUFFIX_ANNOTATIONS: [(&str, u8); 6] = [
+    ("!", 1),
+    ("?", 2),
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+];
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnMove {
+    pub move_code: Move,
+    // numeric annotation glyphs, $1 = good move, $2 = mistake, ...
+    pub nags: Vec<u8>,
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    // alternatives to this move, each one starting from the position before it
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    // 1-0, 0-1, 1/2-1/2 or *
+    pub result: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PgnError {
+    UnterminatedComment,
+    InvalidTag(String),
+    InvalidFen(FenError),
+    // game number starting at 1, error
+    InvalidMove(usize, SanError),
+    UnbalancedVariation,
+    VariationWithoutMove,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::UnterminatedComment => write!(f, "a comment is never closed"),
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair '{}'", tag),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::InvalidMove(game, error) => write!(f, "game {}: {}", game, error),
+            PgnError::UnbalancedVariation => write!(f, "unbalanced parentheses in the movetext"),
+            PgnError::VariationWithoutMove => {
+                write!(f, "a variation must follow the move it replaces")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    TagPair(String, String),
+    Comment(String),
+    Nag(u8),
+    OpenVariation,
+    CloseVariation,
+    GameResult(String),
+    San(String),
+}
+
+fn read_tag_pair(tag: &str) -> Result<Token, PgnError> {
+    let invalid_tag = || PgnError::InvalidTag(tag.to_string());
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid_tag)?;
+    let value = value.trim();
+    if name.is_empty() || value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(invalid_tag());
+    }
+    let value = value[1..value.len() - 1]
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\");
+    Ok(Token::TagPair(name.to_string(), value))
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            // escape mechanism, the whole line is ignored
+            '%' if i == 0 || chars[i - 1] == '\n' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '[' => {
+                // a closing bracket inside the quoted value doesn't end the tag
+                let mut is_quoted = false;
+                i += 1;
+                while i < chars.len() && (is_quoted || chars[i] != ']') {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    } else if chars[i] == '"' {
+                        is_quoted = !is_quoted;
+                    }
+                    i += 1;
+                }
+                let tag: String = chars[start + 1..i.min(chars.len())].iter().collect();
+                if i == chars.len() {
+                    return Err(PgnError::InvalidTag(tag));
+                }
+                tokens.push(read_tag_pair(&tag)?);
+                i += 1;
+            }
+            '{' => {
+                while i < chars.len() && chars[i] != '}' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(PgnError::UnterminatedComment);
+                }
+                let comment: String = chars[start + 1..i].iter().collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                i += 1;
+            }
+            ';' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                let comment: String = chars[start + 1..i].iter().collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => {
+                tokens.push(Token::OpenVariation);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::CloseVariation);
+                i += 1;
+            }
+            letter if letter.is_whitespace() => i += 1,
+            _ => {
+                while i < chars.len() && !chars[i].is_whitespace() && !"[]{}();".contains(chars[i])
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                push_word_tokens(&word, &mut tokens);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// a word of the movetext: nag, result, move number, san with or without suffix annotation
+fn push_word_tokens(word: &str, tokens: &mut Vec<Token>) {
+    if let Some(nag) = word.strip_prefix('$') {
+        if let Ok(nag) = nag.parse() {
+            tokens.push(Token::Nag(nag));
+        }
+        return;
+    }
+    if RESULTS.contains(&word) {
+        tokens.push(Token::GameResult(word.to_string()));
+        return;
+    }
+    // move numbers: 12. 12... or 12.e4 when written without space, the digits must be followed by
+    // a dot to not eat the castling written with zeros (0-0)
+    let digits = word.trim_start_matches(|letter: char| letter.is_ascii_digit());
+    let word = match digits.strip_prefix('.') {
+        Some(_) => digits.trim_start_matches('.'),
+        None => word,
+    };
+    if word.is_empty() {
+        return;
+    }
+    let san = word.trim_end_matches(['!', '?']);
+    tokens.push(Token::San(san.to_string()));
+    if let Some((_, nag)) = SUFFIX_ANNOTATIONS
+        .iter()
+        .find(|(suffix, _)| *suffix == &word[san.len()..])
+    {
+        tokens.push(Token::Nag(*nag));
+    }
+}
+
+struct GameParser<'a> {
+    tokens: &'a [Token],
+    i: usize,
+    ma: &'a MainHashtables,
+    game_number: usize,
+}
+
+impl GameParser<'_> {
+    // parse moves until the end of the variation (or of the game if is_variation is false), all
+    // the moves are played on chessboard, pending_comment goes before the first move
+    fn parse_moves(
+        &mut self,
+        chessboard: &mut ChessBoard,
+        is_variation: bool,
+        mut pending_comment: Option<String>,
+    ) -> Result<Vec<PgnMove>, PgnError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        while self.i < self.tokens.len() {
+            match &self.tokens[self.i] {
+                Token::San(san) => {
+                    let move_code = chessboard
+                        .parse_san(self.ma, san)
+                        .map_err(|error| PgnError::InvalidMove(self.game_number, error))?;
+                    chessboard.make_move(move_code);
+                    moves.push(PgnMove {
+                        move_code,
+                        comment_before: pending_comment.take(),
+                        ..Default::default()
+                    });
+                }
+                Token::Nag(nag) => {
+                    if let Some(last_move) = moves.last_mut() {
+                        last_move.nags.push(*nag);
+                    }
+                }
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last_move) if pending_comment.is_none() => {
+                        append_comment(&mut last_move.comment, comment)
+                    }
+                    _ => append_comment(&mut pending_comment, comment),
+                },
+                Token::OpenVariation => {
+                    let Some(last_move) = moves.last_mut() else {
+                        return Err(PgnError::VariationWithoutMove);
+                    };
+                    self.i += 1;
+                    chessboard.unmake_move();
+                    let variation = self.parse_moves(chessboard, true, None)?;
+                    for _ in 0..variation.len() {
+                        chessboard.unmake_move();
+                    }
+                    chessboard.make_move(last_move.move_code);
+                    last_move.variations.push(variation);
+                }
+                Token::CloseVariation => {
+                    if !is_variation {
+                        return Err(PgnError::UnbalancedVariation);
+                    }
+                    return Ok(moves);
+                }
+                Token::GameResult(_) | Token::TagPair(..) => {
+                    if is_variation {
+                        return Err(PgnError::UnbalancedVariation);
+                    }
+                    return Ok(moves);
+                }
+            }
+            self.i += 1;
+        }
+        if is_variation {
+            return Err(PgnError::UnbalancedVariation);
+        }
+        Ok(moves)
+    }
+}
+
+fn append_comment(comment: &mut Option<String>, text: &str) {
+    match comment {
+        Some(comment) => {
+            comment.push(' ');
+            comment.push_str(text);
+        }
+        None => *comment = Some(text.to_string()),
+    }
+}
+
+// all the games of a pgn file, the moves are checked by replaying them
+pub fn read_pgn(pgn: &str, ma: &MainHashtables) -> Result<Vec<Game>, PgnError> {
+    let tokens = tokenize(pgn)?;
+    let mut games = Vec::new();
+    let mut parser = GameParser {
+        tokens: &tokens,
+        i: 0,
+        ma,
+        game_number: 0,
+    };
+    while parser.i < tokens.len() {
+        // the comments before the tag pairs belong to the game that follows them
+        let mut leading_comment = None;
+        while let Some(Token::Comment(comment)) = tokens.get(parser.i) {
+            append_comment(&mut leading_comment, comment);
+            parser.i += 1;
+        }
+        if parser.i == tokens.len() {
+            break;
+        }
+        parser.game_number += 1;
+        let mut game = Game {
+            tags: Vec::new(),
+            moves: Vec::new(),
+            result: String::from("*"),
+        };
+        while let Some(Token::TagPair(name, value)) = tokens.get(parser.i) {
+            game.tags.push((name.clone(), value.clone()));
+            parser.i += 1;
+        }
+        let mut chessboard = game.get_starting_chessboard()?;
+        game.moves = parser.parse_moves(&mut chessboard, false, leading_comment)?;
+        if let Some(Token::GameResult(result)) = tokens.get(parser.i) {
+            game.result = result.clone();
+            parser.i += 1;
+        } else if let Some(result) = game.get_tag("Result")
+            && RESULTS.contains(&result)
+        {
+            game.result = result.to_string();
+        }
+        games.push(game);
+    }
+    Ok(games)
+}
+
+impl Game {
+    // a game from the starting position with the seven tag roster left unknown
+    pub fn new() -> Game {
+        Game {
+            tags: ["Event", "Site", "Date", "Round", "White", "Black", "Result"]
+                .iter()
+                .map(|name| {
+                    (
+                        name.to_string(),
+                        String::from(if *name == "Result" { "*" } else { "?" }),
+                    )
+                })
+                .collect(),
+            moves: Vec::new(),
+            result: String::from("*"),
+        }
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // the position of the FEN tag if there is one, the starting position otherwise
+    pub fn get_starting_chessboard(&self) -> Result<ChessBoard, PgnError> {
+        ChessBoard::from_fen(self.get_tag("FEN").unwrap_or(STARTING_FEN))
+            .map_err(PgnError::InvalidFen)
+    }
+
+    // the position after the main line
+    pub fn get_final_chessboard(&self) -> Result<ChessBoard, PgnError> {
+        let mut chessboard = self.get_starting_chessboard()?;
+        for pgn_move in &self.moves {
+            chessboard.make_move(pgn_move.move_code);
+        }
+        Ok(chessboard)
+    }
+
+    pub fn write_pgn(&self, ma: &MainHashtables) -> Result<String, PgnError> {
+        let mut pgn = String::new();
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        let mut words = Vec::new();
+        let mut chessboard = self.get_starting_chessboard()?;
+        write_moves(&self.moves, &mut chessboard, ma, &mut words);
+        words.push(self.result.clone());
+
+        // movetext wrapped at MAX_LINE_LENGTH
+        let mut line_length = 0;
+        for word in words {
+            if line_length > 0 && line_length + 1 + word.len() > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += word.len();
+            pgn.push_str(&word);
+        }
+        pgn.push_str("\n\n");
+        Ok(pgn)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+// the moves are played on chessboard to get their san and left played at the end
+fn write_moves(
+    moves: &[PgnMove],
+    chessboard: &mut ChessBoard,
+    ma: &MainHashtables,
+    words: &mut Vec<String>,
+) {
+    // the move number of a black move is only written after something interrupting the moves
+    let mut needs_move_number = true;
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            words.push(format!("{{{}}}", comment));
+            needs_move_number = true;
+        }
+        // the move number is kept on the same line as its move
+        let san = chessboard.get_san(ma, pgn_move.move_code);
+        if chessboard.is_white_to_play {
+            words.push(format!("{}. {}", chessboard.fullmove_number, san));
+        } else if needs_move_number {
+            words.push(format!("{}... {}", chessboard.fullmove_number, san));
+        } else {
+            words.push(san);
+        }
+        needs_move_number = false;
+        for nag in &pgn_move.nags {
+            words.push(format!("${}", nag));
+        }
+        if let Some(comment) = &pgn_move.comment {
+            words.push(format!("{{{}}}", comment));
+            needs_move_number = true;
+        }
+        for variation in &pgn_move.variations {
+            let start = words.len();
+            write_moves(variation, chessboard, ma, words);
+            for _ in 0..variation.len() {
+                chessboard.unmake_move();
+            }
+            if words.len() == start {
+                words.push(String::from("()"));
+            } else {
+                words[start].insert(0, '(');
+                words.last_mut().unwrap().push(')');
+            }
+            needs_move_number = true;
+        }
+        chessboard.make_move(pgn_move.move_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::parse_uci_move;
+
+    const ANNOTATED_GAME: &str = r#"[Event "Casual \"blitz\" game"]
+[Site "?"]
+[Date "2024.05.01"]
+[Round "1"]
+[White "White"]
+[Black "Black"]
+[Result "1-0"]
+
+{The Italian} 1. e4 e5 2. Nf3 $1 Nc6 ; the most played move
+3. Bc4 Bc5!? (3... Nf6 4. Ng5 (4. d3 Be7) 4... d5) 4. 0-0 Nf6 5. d3 d6?! 6. Bg5 h6??
+7. Bxf6 Qxf6 8. Nc3 O-O 1-0
+"#;
+
+    #[test]
+    fn annotated_game_is_read() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let games = read_pgn(ANNOTATED_GAME, &ma).unwrap();
+        assert_eq!(games.len(), 1);
+        let game = &games[0];
+        assert_eq!(game.get_tag("Event"), Some("Casual \"blitz\" game"));
+        assert_eq!(game.result, "1-0");
+        assert_eq!(game.moves.len(), 16);
+        assert_eq!(game.moves[0].comment_before.as_deref(), Some("The Italian"));
+        assert_eq!(game.moves[2].nags, [1]);
+        assert_eq!(
+            game.moves[3].comment.as_deref(),
+            Some("the most played move")
+        );
+        assert_eq!(game.moves[5].nags, [5]);
+        assert_eq!(game.moves[9].nags, [6]);
+        assert_eq!(game.moves[11].nags, [4]);
+
+        // 3... Nf6 4. Ng5 (4. d3 Be7) 4... d5
+        let variation = &game.moves[5].variations[0];
+        assert_eq!(variation.len(), 3);
+        assert_eq!(variation[1].variations[0].len(), 2);
+
+        // castling written with zeros, then with letters
+        let mut chessboard = game.get_starting_chessboard().unwrap();
+        for pgn_move in &game.moves[..6] {
+            chessboard.make_move(pgn_move.move_code);
+        }
+        assert_eq!(
+            game.moves[6].move_code,
+            parse_uci_move(&chessboard, &ma, "e1g1").unwrap()
+        );
+        assert_eq!(
+            game.get_final_chessboard().unwrap().get_fen(),
+            "r1b2rk1/ppp2pp1/2np1q1p/2b1p3/2B1P3/2NP1N2/PPP2PPP/R2Q1RK1 w - - 2 9"
+        );
+    }
+
+    #[test]
+    fn castling_with_zeros_is_read() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let games = read_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 *", &ma).unwrap();
+        assert_eq!(games[0].moves.len(), 8);
+        assert_eq!(games[0].result, "*");
+    }
+
+    #[test]
+    fn comments_before_the_tag_pairs_go_to_the_next_game() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let pgn = "{Annotated} [Event \"first\"]\n\n{Open game} 1. e4 e5 1-0\n\
+                   {Second} [Event \"second\"]\n\n1. d4 * {trailing}";
+        let games = read_pgn(pgn, &ma).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].get_tag("Event"), Some("first"));
+        assert_eq!(
+            games[0].moves[0].comment_before.as_deref(),
+            Some("Annotated Open game")
+        );
+        assert_eq!(games[1].get_tag("Event"), Some("second"));
+        assert_eq!(games[1].moves[0].comment_before.as_deref(), Some("Second"));
+    }
+
+    #[test]
+    fn written_games_are_read_back() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let mut pgn = String::from(ANNOTATED_GAME);
+        for result in RESULTS {
+            pgn.push_str(&format!(
+                "\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n\n1... Kd7 2. e4 {{end}} {}\n",
+                result
+            ));
+        }
+        let games = read_pgn(&pgn, &ma).unwrap();
+        assert_eq!(games.len(), 1 + RESULTS.len());
+        for (game, result) in games[1..].iter().zip(RESULTS) {
+            assert_eq!(game.result, result);
+        }
+        for game in games {
+            let written = game.write_pgn(&ma).unwrap();
+            let read_back = read_pgn(&written, &ma).unwrap();
+            assert_eq!(read_back, [game], "{}", written);
+            assert_eq!(read_back[0].write_pgn(&ma).unwrap(), written);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+
+use crate::ChessBoard;
+use crate::binary_mask::MainHashtables;
+use crate::chess_move::{CastlingSide, Move, MoveKind, PROMOTION_PIECES};
+use crate::get_moves::MovesStruct;
+use crate::notation::{index_to_square, square_to_index};
+
+// by TypePiece as usize % 6
+const PIECE_LETTERS: [char; 6] = ['K', 'Q', 'R', 'B', 'N', 'P'];
+// by PromotionPiece as usize
+const PROMOTION_LETTERS: [char; 4] = ['Q', 'R', 'B', 'N'];
+
+#[derive(Debug, PartialEq)]
+pub enum SanError {
+    InvalidNotation(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::InvalidNotation(san) => write!(f, "'{}' isn't a move in san notation", san),
+            SanError::IllegalMove(san) => write!(f, "'{}' is illegal in this position", san),
+            SanError::AmbiguousMove(san) => {
+                write!(f, "'{}' matches several moves of the position", san)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl ChessBoard {
+    fn get_piece_letter(&self, index: usize) -> char {
+        PIECE_LETTERS[self.pieces_by_index[index] as usize % 6]
+    }
+
+    // standard algebraic notation of a legal move: Nf3, exd5, O-O-O, e8=Q+, Rad1#
+    // the move is played then unmade to know if it gives check
+    pub fn get_san(&mut self, ma: &MainHashtables, move_code: Move) -> String {
+        let mut san = String::new();
+        let from_index = move_code.from_index();
+        let to_index = move_code.to_index();
+
+        match move_code.castling_side() {
+            Some(CastlingSide::WhiteKingSide | CastlingSide::BlackKingSide) => san.push_str("O-O"),
+            Some(_) => san.push_str("O-O-O"),
+            None => {
+                let piece_letter = self.get_piece_letter(from_index);
+                let is_capture =
+                    (self.board & (1 << to_index)) != 0 || move_code.kind() == MoveKind::EnPassant;
+                let from_square = index_to_square(from_index);
+
+                if piece_letter == 'P' {
+                    if is_capture {
+                        san.push_str(&from_square[..1]);
+                    }
+                } else {
+                    san.push(piece_letter);
+                    // disambiguation by file first, then rank, then both
+                    let mut moves = MovesStruct::init();
+                    self.get_moves(ma, &mut moves);
+                    let others: Vec<usize> = moves.moves[..moves.move_number as usize]
+                        .iter()
+                        .filter(|other| {
+                            other.to_index() == to_index
+                                && other.from_index() != from_index
+                                && self.get_piece_letter(other.from_index()) == piece_letter
+                        })
+                        .map(|other| other.from_index())
+                        .collect();
+                    if !others.is_empty() {
+                        if others.iter().all(|other| other % 8 != from_index % 8) {
+                            san.push_str(&from_square[..1]);
+                        } else if others.iter().all(|other| other / 8 != from_index / 8) {
+                            san.push_str(&from_square[1..]);
+                        } else {
+                            san.push_str(&from_square);
+                        }
+                    }
+                }
+                if is_capture {
+                    san.push('x');
+                }
+                san.push_str(&index_to_square(to_index));
+                if let Some(piece) = move_code.promotion_piece() {
+                    san.push('=');
+                    san.push(PROMOTION_LETTERS[piece as usize]);
+                }
+            }
+        }
+
+        // check and checkmate
+        self.make_move(move_code);
+        if self.is_in_check(ma) {
+            let mut moves = MovesStruct::init();
+            self.get_moves(ma, &mut moves);
+            san.push(if moves.move_number == 0 { '#' } else { '+' });
+        }
+        self.unmake_move();
+        san
+    }
+
+    // the legal move matching a san notation, the check/mate suffixes and annotations (!, ?) are
+    // accepted but not verified
+    pub fn parse_san(&self, ma: &MainHashtables, san: &str) -> Result<Move, SanError> {
+        let invalid_notation = || SanError::InvalidNotation(san.to_string());
+        let notation = san.trim_end_matches(['+', '#', '!', '?']);
+        let mut moves = MovesStruct::init();
+        self.get_moves(ma, &mut moves);
+        let moves = &moves.moves[..moves.move_number as usize];
+
+        // castling
+        let castling = match notation {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(is_king_side) = castling {
+            return moves
+                .iter()
+                .find(|move_code| {
+                    move_code.castling_side().is_some_and(|side| {
+                        is_king_side
+                            == matches!(
+                                side,
+                                CastlingSide::WhiteKingSide | CastlingSide::BlackKingSide
+                            )
+                    })
+                })
+                .copied()
+                .ok_or_else(|| SanError::IllegalMove(san.to_string()));
+        }
+
+        if !notation.is_ascii() || notation.len() < 2 {
+            return Err(invalid_notation());
+        }
+        let mut chars: Vec<char> = notation.chars().collect();
+
+        // promotion, e8=Q or e8Q
+        let mut promotion_piece = None;
+        if let Some(i) = PROMOTION_LETTERS
+            .iter()
+            .position(|letter| chars.last() == Some(letter))
+        {
+            promotion_piece = Some(PROMOTION_PIECES[i]);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        // destination
+        if chars.len() < 2 {
+            return Err(invalid_notation());
+        }
+        let to_square: String = chars[chars.len() - 2..].iter().collect();
+        let to_index = square_to_index(&to_square).ok_or_else(invalid_notation)?;
+        chars.truncate(chars.len() - 2);
+
+        // piece, disambiguation and capture
+        let piece_letter = match chars.first() {
+            Some(letter) if PIECE_LETTERS[..5].contains(letter) => chars.remove(0),
+            _ => 'P',
+        };
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+        let mut from_file = None;
+        let mut from_rank = None;
+        for letter in chars {
+            match letter {
+                'a'..='h' if from_file.is_none() => {
+                    from_file = Some(letter as usize - 'a' as usize)
+                }
+                '1'..='8' if from_rank.is_none() => {
+                    from_rank = Some('8' as usize - letter as usize)
+                }
+                _ => return Err(invalid_notation()),
+            }
+        }
+
+        let mut candidates = moves.iter().filter(|move_code| {
+            let from_index = move_code.from_index();
+            move_code.castling_side().is_none()
+                && move_code.to_index() == to_index
+                && move_code.promotion_piece() == promotion_piece
+                && self.get_piece_letter(from_index) == piece_letter
+                && from_file.is_none_or(|x| from_index % 8 == x)
+                && from_rank.is_none_or(|y| from_index / 8 == y)
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(move_code), None) => Ok(*move_code),
+            (None, _) => Err(SanError::IllegalMove(san.to_string())),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::parse_uci_move;
+
+    #[test]
+    fn san_is_written_and_parsed_back() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "g1f3",
+                "Nf3",
+            ),
+            ("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1", "a1d1", "Rad1"),
+            ("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1", "f1d1", "Rfd1"),
+            ("4k3/8/8/8/8/3R4/8/3R2K1 w - - 0 1", "d1d2", "R1d2"),
+            ("4k3/8/8/8/8/3R4/8/3R2K1 w - - 0 1", "d3d2", "R3d2"),
+            ("8/k7/8/8/4Q2Q/8/8/K6Q w - - 0 1", "h4e1", "Qh4e1"),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", "Ra8+"),
+            ("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1", "d1d8", "Rd8#"),
+            ("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q", "e8=Q"),
+            ("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8n", "e8=N"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "O-O"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "O-O-O"),
+        ];
+        for (fen, uci, san) in positions {
+            let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+            let move_code = parse_uci_move(&chessboard, &ma, uci).unwrap();
+            assert_eq!(chessboard.get_san(&ma, move_code), san, "{}", fen);
+            assert_eq!(chessboard.parse_san(&ma, san), Ok(move_code), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn alternative_spellings_are_parsed() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let positions = [
+            ("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q", "e8Q"),
+            ("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8r", "e8=R+"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "0-0"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1", "0-0-0"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1", "O-O-O!?"),
+            ("8/k7/8/8/4Q2Q/8/8/K6Q w - - 0 1", "h1e1", "Q1e1"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "ed6"),
+        ];
+        for (fen, uci, san) in positions {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            let move_code = parse_uci_move(&chessboard, &ma, uci).unwrap();
+            assert_eq!(chessboard.parse_san(&ma, san), Ok(move_code), "{}", san);
+        }
+    }
+
+    #[test]
+    fn invalid_san_is_rejected() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let positions = [
+            (
+                "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1",
+                "Rd1",
+                SanError::AmbiguousMove("Rd1".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/3R4/8/3R2K1 w - - 0 1",
+                "Rd2",
+                SanError::AmbiguousMove("Rd2".to_string()),
+            ),
+            (
+                "8/k7/8/8/4Q2Q/8/8/K6Q w - - 0 1",
+                "Qhe1",
+                SanError::AmbiguousMove("Qhe1".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1",
+                "Nf3",
+                SanError::IllegalMove("Nf3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1",
+                "O-O",
+                SanError::IllegalMove("O-O".to_string()),
+            ),
+            // a promotion without the piece
+            (
+                "8/4P3/8/8/8/8/k7/4K3 w - - 0 1",
+                "e8",
+                SanError::IllegalMove("e8".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1",
+                "Ri9",
+                SanError::InvalidNotation("Ri9".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1",
+                "x",
+                SanError::InvalidNotation("x".to_string()),
+            ),
+        ];
+        for (fen, san, error) in positions {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(chessboard.parse_san(&ma, san), Err(error), "{}", san);
+        }
+    }
+}
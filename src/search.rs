@@ -0,0 +1,473 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::ChessBoard;
+use crate::binary_mask::MainHashtables;
+use crate::chess_move::Move;
+use crate::evaluate::evaluate;
+use crate::move_picker::{History, MovePicker};
+use crate::time_manager::TimeManager;
+use crate::transposition_table::{Bound, TranspositionTable};
+
+pub const MAX_PLY: usize = 64;
+pub const INFINITY: i32 = 32000;
+// a mate in n plies is scored MATE_SCORE - n
+pub const MATE_SCORE: i32 = 31000;
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub time_manager: Option<TimeManager>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    // centipawns from the point of view of the player to play
+    pub score: i32,
+    pub pv: Vec<Move>,
+    pub nodes: u64,
+    pub depth: u8,
+}
+
+impl SearchResult {
+    // number of moves (not plies) until mate, negative if the player to play is mated
+    pub fn get_mate_in(&self) -> Option<i32> {
+        if self.score.abs() < MATE_SCORE - MAX_PLY as i32 {
+            return None;
+        }
+        let plies = MATE_SCORE - self.score.abs();
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+pub struct Search<'a> {
+    ma: &'a MainHashtables,
+    stop: &'a AtomicBool,
+    tt: &'a mut TranspositionTable,
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    // the first iteration always completes so there is a move to play
+    can_stop: bool,
+    is_stopped: bool,
+    // triangular table, pv[ply][ply..pv_length[ply]] is the best line found from ply
+    pv: [[Move; MAX_PLY]; MAX_PLY],
+    pv_length: [usize; MAX_PLY],
+    // quiet moves that caused a cutoff at the same ply, tried right after the captures
+    killers: [[Move; 2]; MAX_PLY],
+    history: History,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(
+        ma: &'a MainHashtables,
+        stop: &'a AtomicBool,
+        tt: &'a mut TranspositionTable,
+        limits: SearchLimits,
+    ) -> Search<'a> {
+        Search {
+            ma,
+            stop,
+            tt,
+            limits,
+            start: Instant::now(),
+            nodes: 0,
+            can_stop: false,
+            is_stopped: false,
+            pv: [[Move::NULL; MAX_PLY]; MAX_PLY],
+            pv_length: [0; MAX_PLY],
+            killers: [[Move::NULL; 2]; MAX_PLY],
+            history: [[0; 64]; 12],
+        }
+    }
+
+    // checked every few nodes, the stop flag can be set from another thread
+    fn should_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+            || self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || self
+                .limits
+                .movetime
+                .is_some_and(|movetime| self.start.elapsed() >= movetime)
+            || self
+                .limits
+                .time_manager
+                .as_ref()
+                .is_some_and(|time_manager| self.start.elapsed() >= time_manager.get_hard_limit())
+    }
+
+    // iterative deepening, on_iteration is called with the result of each completed depth
+    pub fn run(
+        &mut self,
+        chessboard: &mut ChessBoard,
+        mut on_iteration: impl FnMut(&SearchResult, Duration),
+    ) -> SearchResult {
+        self.start = Instant::now();
+        self.tt.new_search();
+        let mut result = SearchResult::default();
+        let max_depth = self
+            .limits
+            .depth
+            .unwrap_or(MAX_PLY as u8 - 1)
+            .min(MAX_PLY as u8 - 1);
+        for depth in 1..=max_depth {
+            self.can_stop = depth > 1;
+            let score = self.negamax(chessboard, depth, 0, -INFINITY, INFINITY);
+            // the result of an interrupted iteration can't be trusted
+            if self.is_stopped {
+                break;
+            }
+            result = SearchResult {
+                best_move: (self.pv_length[0] > 0).then_some(self.pv[0][0]),
+                score,
+                pv: self.pv[0][..self.pv_length[0]].to_vec(),
+                nodes: self.nodes,
+                depth,
+            };
+            on_iteration(&result, self.start.elapsed());
+            if result.best_move.is_none() {
+                break;
+            }
+            if let Some(time_manager) = &mut self.limits.time_manager
+                && !time_manager.should_start_iteration(&result, self.start.elapsed())
+            {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn is_stopped_at_node(&mut self) -> bool {
+        if self.can_stop && self.nodes & 2047 == 0 && self.should_stop() {
+            self.is_stopped = true;
+        }
+        self.is_stopped
+    }
+
+    fn update_quiet_heuristics(
+        &mut self,
+        chessboard: &ChessBoard,
+        move_code: Move,
+        depth: u8,
+        ply: usize,
+    ) {
+        if self.killers[ply][0] != move_code {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = move_code;
+        }
+        let piece = chessboard.pieces_by_index[move_code.from_index()] as usize;
+        let entry = &mut self.history[piece][move_code.to_index()];
+        *entry += depth as i32 * depth as i32;
+        // keeps the scores bounded in long searches
+        if *entry > 1 << 20 {
+            for scores in self.history.iter_mut() {
+                for score in scores.iter_mut() {
+                    *score /= 2;
+                }
+            }
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        chessboard: &mut ChessBoard,
+        depth: u8,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.pv_length[ply] = ply;
+        if depth == 0 {
+            return self.quiescence(chessboard, ply, alpha, beta);
+        }
+        if self.is_stopped_at_node() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0 && is_draw(chessboard) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(chessboard, self.ma);
+        }
+
+        let hash = chessboard.get_hash();
+        let tt_entry = self.tt.probe(hash);
+        // no cutoff at the root, a move has to be given back
+        if let Some(entry) = tt_entry
+            && ply > 0
+            && entry.depth >= depth
+        {
+            let score = entry.get_score(ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        let tt_move = tt_entry.map_or(Move::NULL, |entry| entry.move_code);
+        let mut move_picker = MovePicker::new(tt_move, self.killers[ply]);
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = Move::NULL;
+        let mut move_number = 0;
+        while let Some(move_code) = move_picker.next(chessboard, self.ma, &self.history) {
+            move_number += 1;
+            let is_quiet = chessboard.is_quiet(move_code);
+            chessboard.make_move(move_code);
+            let score = -self.negamax(chessboard, depth - 1, ply + 1, -beta, -alpha);
+            chessboard.unmake_move();
+            if self.is_stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = move_code;
+            }
+            if score > alpha {
+                alpha = score;
+                self.pv[ply][ply] = move_code;
+                for i in ply + 1..self.pv_length[ply + 1] {
+                    self.pv[ply][i] = self.pv[ply + 1][i];
+                }
+                self.pv_length[ply] = self.pv_length[ply + 1];
+                if alpha >= beta {
+                    if is_quiet {
+                        self.update_quiet_heuristics(chessboard, move_code, depth, ply);
+                    }
+                    break;
+                }
+            }
+        }
+        if move_number == 0 {
+            if chessboard.is_in_check(self.ma) {
+                return -MATE_SCORE + ply as i32;
+            }
+            return 0;
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt
+            .store(hash, depth, bound, best_score, best_move, ply);
+        best_score
+    }
+
+    // only captures and promotions are searched until the position is quiet, the player to play
+    // can also stand pat and keep the static evaluation
+    // the captures losing material according to see are pruned by the move picker
+    // in check there is no standing pat, every evasion is searched so the mates are seen
+    fn quiescence(
+        &mut self,
+        chessboard: &mut ChessBoard,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.is_stopped_at_node() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if is_draw(chessboard) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(chessboard, self.ma);
+        }
+        let is_in_check = chessboard.is_in_check(self.ma);
+        let mut best_score = -INFINITY;
+        if !is_in_check {
+            let stand_pat = evaluate(chessboard, self.ma);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            best_score = stand_pat;
+        }
+
+        let mut move_picker = if is_in_check {
+            MovePicker::new_evasions()
+        } else {
+            MovePicker::new_captures()
+        };
+        let mut move_number = 0;
+        while let Some(move_code) = move_picker.next(chessboard, self.ma, &self.history) {
+            move_number += 1;
+            chessboard.make_move(move_code);
+            let score = -self.quiescence(chessboard, ply + 1, -beta, -alpha);
+            chessboard.unmake_move();
+            if self.is_stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+            }
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        if is_in_check && move_number == 0 {
+            return -MATE_SCORE + ply as i32;
+        }
+        best_score
+    }
+}
+
+fn is_draw(chessboard: &ChessBoard) -> bool {
+    chessboard.halfmove_clock >= 100
+        || chessboard.count_repetitions() > 0
+        || chessboard.is_insufficient_material()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::get_moves::MovesStruct;
+    use crate::notation::{STARTING_FEN, parse_uci_move};
+    use crate::outcome::Outcome;
+
+    fn search(
+        ma: &MainHashtables,
+        fen: &str,
+        stop: &AtomicBool,
+        limits: SearchLimits,
+    ) -> SearchResult {
+        let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+        let mut tt = TranspositionTable::new(1);
+        Search::new(ma, stop, &mut tt, limits).run(&mut chessboard, |_, _| {})
+    }
+
+    fn is_legal(ma: &MainHashtables, fen: &str, move_code: Move) -> bool {
+        let chessboard = ChessBoard::from_fen(fen).unwrap();
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves(ma, &mut moves);
+        moves.moves[..moves.move_number as usize].contains(&move_code)
+    }
+
+    #[test]
+    fn mates_are_found() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..Default::default()
+        };
+
+        let fen = "6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1";
+        let result = search(&ma, fen, &stop, limits.clone());
+        let chessboard = ChessBoard::from_fen(fen).unwrap();
+        assert_eq!(
+            result.best_move,
+            parse_uci_move(&chessboard, &ma, "d1d8").ok()
+        );
+        assert_eq!(result.score, MATE_SCORE - 1);
+        assert_eq!(result.get_mate_in(), Some(1));
+
+        // the rook ladder, Ra7 or Rb7 first
+        let fen = "6k1/8/8/8/8/8/R7/1R4K1 w - - 0 1";
+        let result = search(&ma, fen, &stop, limits.clone());
+        assert_eq!(result.score, MATE_SCORE - 3);
+        assert_eq!(result.get_mate_in(), Some(2));
+        let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+        for move_code in &result.pv[..3] {
+            chessboard.make_move(*move_code);
+        }
+        assert_eq!(
+            chessboard.get_outcome(&ma),
+            Outcome::Checkmate {
+                is_white_winner: true
+            }
+        );
+
+        // already mated, there is no move to give back
+        let result = search(&ma, "3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", &stop, limits);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, -MATE_SCORE);
+        assert_eq!(result.get_mate_in(), Some(0));
+    }
+
+    #[test]
+    fn mates_at_the_horizon_are_found() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits {
+            depth: Some(1),
+            ..Default::default()
+        };
+
+        // the mate is only seen by the quiescence search, which can't stand pat in check
+        let fen = "6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1";
+        let result = search(&ma, fen, &stop, limits);
+        let chessboard = ChessBoard::from_fen(fen).unwrap();
+        assert_eq!(
+            result.best_move,
+            parse_uci_move(&chessboard, &ma, "d1d8").ok()
+        );
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn draws_are_scored_zero() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..Default::default()
+        };
+
+        let result = search(&ma, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", &stop, limits.clone());
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, 0);
+
+        // taking the queen leaves a lone knight, everything else loses
+        let fen = "k7/8/8/8/3q4/5N2/8/7K w - - 0 1";
+        let result = search(&ma, fen, &stop, limits);
+        let chessboard = ChessBoard::from_fen(fen).unwrap();
+        assert_eq!(
+            result.best_move,
+            parse_uci_move(&chessboard, &ma, "f3d4").ok()
+        );
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn search_is_interrupted() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+
+        // the first iteration always completes, then the flag is read every 2048 nodes
+        let stop = AtomicBool::new(true);
+        let result = search(&ma, STARTING_FEN, &stop, SearchLimits::default());
+        assert!(result.depth >= 1 && result.nodes < 2 * 2048);
+        assert!(is_legal(&ma, STARTING_FEN, result.best_move.unwrap()));
+
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits {
+            nodes: Some(10000),
+            ..Default::default()
+        };
+        let result = search(&ma, STARTING_FEN, &stop, limits);
+        assert!(result.depth < MAX_PLY as u8 - 1);
+        assert!(result.nodes < 10000 + 2048);
+        assert!(is_legal(&ma, STARTING_FEN, result.best_move.unwrap()));
+    }
+}
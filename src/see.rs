@@ -0,0 +1,126 @@
+use crate::binary_mask::MainHashtables;
+use crate::chess_move::{Move, MoveKind};
+use crate::evaluate::PIECE_VALUES;
+use crate::{ChessBoard, TypePiece};
+
+// the king is worth more than anything else so it only takes when nothing can take it back
+const SEE_KING_VALUE: i32 = 20000;
+
+// value by TypePiece as usize % 6
+fn get_see_value(piece: usize) -> i32 {
+    if piece == TypePiece::WhiteKing as usize {
+        SEE_KING_VALUE
+    } else {
+        PIECE_VALUES[piece]
+    }
+}
+
+impl ChessBoard {
+    // static exchange evaluation, material won by the player to play if both sides keep taking on
+    // the destination square of move_code with their least valuable piece for as long as it pays
+    // pins and checks are ignored
+    pub fn see(&self, ma: &MainHashtables, move_code: Move) -> i32 {
+        if move_code.kind() == MoveKind::Castling {
+            return 0;
+        }
+        let from_index = move_code.from_index();
+        let to_index = move_code.to_index();
+        let mut occupancy = self.board ^ (1 << from_index);
+        // gains[depth] is the material won by the side taking at depth if the exchange stopped there
+        let mut gains = [0; 32];
+        gains[0] = match move_code.kind() {
+            MoveKind::EnPassant => {
+                let taken_pawn = if self.is_white_to_play {
+                    1 << (to_index + 8)
+                } else {
+                    1 << (to_index - 8)
+                };
+                occupancy ^= taken_pawn;
+                PIECE_VALUES[TypePiece::WhitePawn as usize]
+            }
+            _ => PIECE_VALUES[self.pieces_by_index[to_index] as usize % 6],
+        };
+        // value of the piece standing on the square, the next one to be taken
+        let mut piece_value = get_see_value(self.pieces_by_index[from_index] as usize % 6);
+        if let Some(promotion_piece) = move_code.promotion_piece() {
+            piece_value = PIECE_VALUES[promotion_piece as usize + 1];
+            gains[0] += piece_value - PIECE_VALUES[TypePiece::WhitePawn as usize];
+        }
+
+        // color as in players
+        let mut color = !self.is_white_to_play as usize;
+        let mut depth = 0;
+        while depth + 1 < gains.len() {
+            // the attackers are recomputed with the new occupancy so the x-rays are found
+            let attackers = self.get_attackers(ma, to_index as u8, color, occupancy) & occupancy;
+            if attackers == 0 {
+                break;
+            }
+            depth += 1;
+            gains[depth] = piece_value - gains[depth - 1];
+
+            let offset = if color == 1 { 0 } else { 6 };
+            // the least valuable attacker takes, from the pawns to the king
+            let piece = (0..6)
+                .rev()
+                .find(|piece| self.pieces[piece + offset] & attackers != 0)
+                .unwrap();
+            let attacker = self.pieces[piece + offset] & attackers;
+            occupancy ^= attacker & attacker.wrapping_neg();
+            piece_value = get_see_value(piece);
+            color = 1 - color;
+        }
+
+        // each side only takes if it is better than stopping the exchange
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+        gains[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, generate_main_hashtables};
+    use crate::notation::parse_uci_move;
+
+    #[test]
+    fn static_exchange_evaluation() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        let positions = [
+            // free pawn
+            (
+                "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+                "e1e5",
+                100,
+            ),
+            // the knight takes a defended pawn
+            (
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5",
+                100 - 320,
+            ),
+            // the queen behind the bishop takes back through the x-ray
+            ("4k3/8/2b5/3p4/4B3/8/6Q1/6K1 w - - 0 1", "e4d5", 100),
+            // the doubled rooks win the pawn
+            ("3rk3/8/8/3p4/8/8/3R4/3R2K1 w - - 0 1", "d2d5", 100),
+            // against doubled rooks the exchange loses a rook for a pawn
+            ("3rk3/3r4/8/3p4/8/8/3R4/3R2K1 w - - 0 1", "d2d5", 100 - 500),
+            // en-passant on an undefended pawn
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 100),
+        ];
+        for (fen, notation, expected) in positions {
+            let chessboard = ChessBoard::from_fen(fen).unwrap();
+            let move_code = parse_uci_move(&chessboard, &ma, notation).unwrap();
+            assert_eq!(
+                chessboard.see(&ma, move_code),
+                expected,
+                "{} {}",
+                fen,
+                notation
+            );
+        }
+    }
+}
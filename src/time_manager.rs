@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::chess_move::Move;
+use crate::search::SearchResult;
+
+// kept for the communication with the gui so the engine doesn't lose on time
+const MOVE_OVERHEAD: Duration = Duration::from_millis(20);
+// used when the gui doesn't give movestogo
+const DEFAULT_MOVES_TO_GO: u32 = 25;
+// the hard limit can't use more than this part of the remaining time
+const MAX_TIME_PART: f64 = 0.5;
+// by number of iterations the best move stayed the same
+const STABILITY_FACTORS: [f64; 5] = [2.0, 1.4, 1.0, 0.8, 0.7];
+// centipawns lost since the previous iteration from which more time is given
+const SCORE_DROP_THRESHOLD: i32 = 30;
+const MAX_SCORE_DROP_FACTOR: f64 = 2.0;
+
+// chooses how long to think with a clock
+// the search never goes past the hard limit, a new iteration isn't started past the soft limit
+// scaled by how stable the best move and the score are
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    soft_limit: Duration,
+    hard_limit: Duration,
+    best_move: Option<Move>,
+    stability: usize,
+    previous_score: Option<i32>,
+}
+
+impl TimeManager {
+    pub fn new(time: Duration, increment: Duration, moves_to_go: Option<u32>) -> TimeManager {
+        let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let time = time.saturating_sub(MOVE_OVERHEAD);
+        let max_time = time.mul_f64(MAX_TIME_PART);
+        let soft_limit = (time / moves_to_go + increment.mul_f64(0.75)).min(max_time);
+        // with a single move left before the time control the whole budget can be used at once
+        let hard_limit = if moves_to_go == 1 {
+            max_time
+        } else {
+            (soft_limit * 4).min(max_time)
+        };
+        TimeManager {
+            soft_limit,
+            hard_limit,
+            best_move: None,
+            stability: 0,
+            previous_score: None,
+        }
+    }
+
+    pub fn get_hard_limit(&self) -> Duration {
+        self.hard_limit
+    }
+
+    // called after each completed iteration, returns whether the next one should be started
+    pub fn should_start_iteration(&mut self, result: &SearchResult, elapsed: Duration) -> bool {
+        if result.best_move == self.best_move {
+            self.stability += 1;
+        } else {
+            self.stability = 0;
+            self.best_move = result.best_move;
+        }
+        let mut factor = STABILITY_FACTORS[self.stability.min(STABILITY_FACTORS.len() - 1)];
+        if let Some(previous_score) = self.previous_score {
+            let drop = previous_score - result.score;
+            if drop > SCORE_DROP_THRESHOLD {
+                factor *= (1.0 + drop as f64 / 100.0).min(MAX_SCORE_DROP_FACTOR);
+            }
+        }
+        self.previous_score = Some(result.score);
+
+        elapsed < self.soft_limit.mul_f64(factor).min(self.hard_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_result(best_move: Move, score: i32) -> SearchResult {
+        SearchResult {
+            best_move: Some(best_move),
+            score,
+            ..SearchResult::default()
+        }
+    }
+
+    #[test]
+    fn limits_stay_within_the_remaining_time() {
+        let time = Duration::from_secs(60);
+        let time_manager = TimeManager::new(time, Duration::from_secs(1), None);
+        assert!(time_manager.soft_limit < time_manager.hard_limit);
+        assert!(time_manager.hard_limit <= time / 2);
+
+        let time_manager = TimeManager::new(Duration::from_millis(10), Duration::ZERO, Some(1));
+        assert_eq!(time_manager.hard_limit, Duration::ZERO);
+    }
+
+    #[test]
+    fn stable_best_move_stops_early_and_score_drop_extends() {
+        let time = Duration::from_secs(25);
+        let first_move = Move::new(52, 36);
+        let second_move = Move::new(51, 35);
+
+        let mut stable = TimeManager::new(time, Duration::ZERO, None);
+        let elapsed = stable.soft_limit.mul_f64(0.9);
+        for _ in 0..4 {
+            stable.should_start_iteration(&get_result(first_move, 20), Duration::ZERO);
+        }
+        assert!(!stable.should_start_iteration(&get_result(first_move, 20), elapsed));
+
+        let mut unstable = TimeManager::new(time, Duration::ZERO, None);
+        unstable.should_start_iteration(&get_result(first_move, 20), Duration::ZERO);
+        let elapsed = unstable.soft_limit.mul_f64(1.5);
+        assert!(unstable.should_start_iteration(&get_result(second_move, 20), elapsed));
+
+        let mut dropping = TimeManager::new(time, Duration::ZERO, None);
+        for _ in 0..4 {
+            dropping.should_start_iteration(&get_result(first_move, 20), Duration::ZERO);
+        }
+        let elapsed = dropping.soft_limit.mul_f64(1.2);
+        assert!(dropping.should_start_iteration(&get_result(first_move, -80), elapsed));
+    }
+}
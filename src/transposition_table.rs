@@ -0,0 +1,144 @@
+use crate::chess_move::Move;
+use crate::search::{MATE_SCORE, MAX_PLY};
+
+pub const DEFAULT_HASH_SIZE: usize = 16;
+pub const MAX_HASH_SIZE: usize = 4096;
+
+// how the stored score relates to the real score of the position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    // the search failed high, the real score is at least the stored one
+    Lower,
+    // the search failed low, the real score is at most the stored one
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TtEntry {
+    // lower bits of the hash, the upper ones are already used to find the slot
+    key: u32,
+    pub move_code: Move,
+    score: i16,
+    pub depth: u8,
+    pub bound: Bound,
+    age: u8,
+}
+
+impl TtEntry {
+    // mate scores are stored relative to the position and given back relative to the root
+    pub fn get_score(&self, ply: usize) -> i32 {
+        let score = self.score as i32;
+        if score >= MATE_SCORE - MAX_PLY as i32 {
+            score - ply as i32
+        } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+            score + ply as i32
+        } else {
+            score
+        }
+    }
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+    // incremented at every search so entries of previous searches are replaced first
+    age: u8,
+}
+
+impl TranspositionTable {
+    // size in megabytes
+    pub fn new(size: usize) -> TranspositionTable {
+        let mut tt = TranspositionTable {
+            entries: Vec::new(),
+            age: 0,
+        };
+        tt.resize(size);
+        tt
+    }
+
+    // size in megabytes, the content is lost
+    pub fn resize(&mut self, size: usize) {
+        let entry_number = (size.clamp(1, MAX_HASH_SIZE) << 20) / size_of::<Option<TtEntry>>();
+        self.entries = vec![None; entry_number];
+        self.age = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.age = 0;
+    }
+
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    fn get_index(&self, hash: u64) -> usize {
+        // maps the hash on 0..len without needing a power of two number of entries
+        ((hash as u128 * self.entries.len() as u128) >> 64) as usize
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<TtEntry> {
+        self.entries[self.get_index(hash)].filter(|entry| entry.key == hash as u32)
+    }
+
+    pub fn store(
+        &mut self,
+        hash: u64,
+        depth: u8,
+        bound: Bound,
+        score: i32,
+        move_code: Move,
+        ply: usize,
+    ) {
+        let index = self.get_index(hash);
+        let key = hash as u32;
+        // an entry is kept only if it is from the current search and was searched deeper
+        if let Some(entry) = self.entries[index]
+            && entry.age == self.age
+            && entry.key != key
+            && entry.depth > depth
+            && bound != Bound::Exact
+        {
+            return;
+        }
+        // a new result without move shouldn't erase the move of the same position
+        let move_code = match self.entries[index] {
+            Some(entry) if entry.key == key && move_code == Move::NULL => entry.move_code,
+            _ => move_code,
+        };
+
+        let score = if score >= MATE_SCORE - MAX_PLY as i32 {
+            score + ply as i32
+        } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+            score - ply as i32
+        } else {
+            score
+        };
+        self.entries[index] = Some(TtEntry {
+            key,
+            move_code,
+            score: score as i16,
+            depth,
+            bound,
+            age: self.age,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mate_scores_are_relative_to_the_position() {
+        let mut tt = TranspositionTable::new(1);
+        let hash = 0x123456789abcdef0;
+        // mate in 3 plies found at ply 5
+        tt.store(hash, 4, Bound::Exact, MATE_SCORE - 8, Move::NULL, 5);
+        let entry = tt.probe(hash).unwrap();
+        assert_eq!(entry.get_score(5), MATE_SCORE - 8);
+        // the same position reached at ply 1 is a mate in 4 plies from the root
+        assert_eq!(entry.get_score(1), MATE_SCORE - 4);
+        assert!(tt.probe(hash ^ 1).is_none());
+    }
+}
@@ -0,0 +1,261 @@
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::ChessBoard;
+use crate::binary_mask::MainHashtables;
+use crate::notation::{STARTING_FEN, parse_uci_move};
+use crate::search::{Search, SearchLimits, SearchResult};
+use crate::time_manager::TimeManager;
+use crate::transposition_table::{DEFAULT_HASH_SIZE, MAX_HASH_SIZE, TranspositionTable};
+
+// parameters of the go command, times are in milliseconds
+#[derive(Clone, Debug, Default)]
+pub struct GoParameters {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+}
+
+impl GoParameters {
+    pub fn parse(tokens: &[&str]) -> GoParameters {
+        let mut parameters = GoParameters::default();
+        let mut i = 0;
+        while i < tokens.len() {
+            let value = tokens.get(i + 1);
+            let parse_u64 = || value.and_then(|value| value.parse::<u64>().ok());
+            match tokens[i] {
+                "depth" => parameters.depth = value.and_then(|value| value.parse().ok()),
+                "nodes" => parameters.nodes = parse_u64(),
+                "movetime" => parameters.movetime = parse_u64(),
+                "wtime" => parameters.wtime = parse_u64(),
+                "btime" => parameters.btime = parse_u64(),
+                "winc" => parameters.winc = parse_u64(),
+                "binc" => parameters.binc = parse_u64(),
+                "movestogo" => parameters.movestogo = value.and_then(|value| value.parse().ok()),
+                "infinite" => {
+                    parameters.infinite = true;
+                    i += 1;
+                    continue;
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+        }
+        parameters
+    }
+}
+
+impl GoParameters {
+    fn get_search_limits(&self, is_white_to_play: bool) -> SearchLimits {
+        let (time, increment) = if is_white_to_play {
+            (self.wtime, self.winc)
+        } else {
+            (self.btime, self.binc)
+        };
+        // a fixed movetime or infinite mode ignores the clock
+        let time_manager = time
+            .filter(|_| self.movetime.is_none() && !self.infinite)
+            .map(|time| {
+                TimeManager::new(
+                    Duration::from_millis(time),
+                    Duration::from_millis(increment.unwrap_or(0)),
+                    self.movestogo,
+                )
+            });
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            movetime: self
+                .movetime
+                .filter(|_| !self.infinite)
+                .map(Duration::from_millis),
+            time_manager,
+        }
+    }
+}
+
+fn print_info(result: &SearchResult, elapsed: Duration) {
+    let score = match result.get_mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let milliseconds = elapsed.as_millis() as u64;
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|move_code| move_code.to_string())
+        .collect();
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.nodes * 1000 / milliseconds.max(1),
+        milliseconds,
+        pv.join(" ")
+    );
+}
+
+pub struct Uci {
+    ma: Arc<MainHashtables>,
+    chessboard: ChessBoard,
+    stop: Arc<AtomicBool>,
+    // only used by the search thread while it runs
+    tt: Arc<Mutex<TranspositionTable>>,
+    search_thread: Option<JoinHandle<()>>,
+}
+
+impl Uci {
+    pub fn new(ma: MainHashtables) -> Uci {
+        Uci {
+            ma: Arc::new(ma),
+            chessboard: ChessBoard::from_fen(STARTING_FEN).unwrap(),
+            stop: Arc::new(AtomicBool::new(false)),
+            tt: Arc::new(Mutex::new(TranspositionTable::new(DEFAULT_HASH_SIZE))),
+            search_thread: None,
+        }
+    }
+
+    // read the commands on stdin until quit or the end of the input
+    pub fn run(&mut self) {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if !self.handle_command(&line) {
+                break;
+            }
+        }
+        self.stop_search();
+    }
+
+    // returns false when the engine must quit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first() {
+            Some(&"uci") => {
+                println!("id name pomme_chess");
+                println!("id author pommeJedusor");
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_SIZE, MAX_HASH_SIZE
+                );
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"setoption") => {
+                self.stop_search();
+                self.set_option(&tokens[1..]);
+            }
+            Some(&"ucinewgame") => {
+                self.stop_search();
+                self.chessboard = ChessBoard::from_fen(STARTING_FEN).unwrap();
+                self.tt.lock().unwrap().clear();
+            }
+            Some(&"position") => {
+                self.stop_search();
+                self.set_position(&tokens[1..]);
+            }
+            Some(&"go") => {
+                self.stop_search();
+                self.go(GoParameters::parse(&tokens[1..]));
+            }
+            Some(&"stop") => self.stop_search(),
+            Some(&"quit") => return false,
+            _ => {}
+        }
+        true
+    }
+
+    // setoption name <name> [value <value>]
+    fn set_option(&mut self, tokens: &[&str]) {
+        let value_index = tokens
+            .iter()
+            .position(|token| *token == "value")
+            .unwrap_or(tokens.len());
+        let name = tokens[1.min(value_index)..value_index].join(" ");
+        let value = tokens[(value_index + 1).min(tokens.len())..].join(" ");
+        match name.to_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(size) => self.tt.lock().unwrap().resize(size),
+                Err(_) => println!("info string invalid hash size: {}", value),
+            },
+            _ => println!("info string unknown option: {}", name),
+        }
+    }
+
+    // position startpos|fen <fen> [moves <move>...]
+    fn set_position(&mut self, tokens: &[&str]) {
+        let moves_index = tokens
+            .iter()
+            .position(|token| *token == "moves")
+            .unwrap_or(tokens.len());
+        let fen = match tokens.first() {
+            Some(&"startpos") => STARTING_FEN.to_string(),
+            Some(&"fen") => tokens[1..moves_index].join(" "),
+            _ => {
+                println!("info string expected startpos or fen after position");
+                return;
+            }
+        };
+        self.chessboard = match ChessBoard::from_fen(&fen) {
+            Ok(chessboard) => chessboard,
+            Err(error) => {
+                println!("info string invalid fen: {}", error);
+                return;
+            }
+        };
+
+        for notation in tokens.iter().skip(moves_index + 1) {
+            match parse_uci_move(&self.chessboard, &self.ma, notation) {
+                Ok(move_code) => self.chessboard.make_move(move_code),
+                Err(error) => {
+                    println!("info string {}", error);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn go(&mut self, parameters: GoParameters) {
+        self.stop.store(false, Ordering::Relaxed);
+        let mut chessboard = self.chessboard.clone();
+        let ma = Arc::clone(&self.ma);
+        let stop = Arc::clone(&self.stop);
+        let tt = Arc::clone(&self.tt);
+        let limits = parameters.get_search_limits(chessboard.is_white_to_play);
+        self.search_thread = Some(thread::spawn(move || {
+            let result = {
+                let mut tt = tt.lock().unwrap();
+                Search::new(&ma, &stop, &mut tt, limits).run(&mut chessboard, print_info)
+            };
+            // in infinite mode the best move can only be sent once stop has been received
+            while parameters.infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            match result.best_move {
+                Some(best_move) => println!("bestmove {}", best_move),
+                None => println!("bestmove 0000"),
+            }
+        }));
+    }
+
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(search_thread) = self.search_thread.take() {
+            search_thread.join().unwrap();
+        }
+    }
+}
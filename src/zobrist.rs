@@ -0,0 +1,193 @@
+use crate::{ChessBoard, TypePiece};
+
+pub struct ZobristKeys {
+    // pieces[type_piece][index], the row of TypePiece::Empty is only zeros so a capture on an
+    // empty square doesn't change the hash
+    pub pieces: [[u64; 64]; 13],
+    pub black_to_play: u64,
+    // white king side, white queen side, black king side, black queen side
+    pub castling: [u64; 4],
+    pub en_passant_file: [u64; 8],
+}
+
+// splitmix64, a fixed algorithm so the keys and the magic numbers searched from a seed are the same
+// on every run and with every version of the dependencies
+pub const fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+const fn generate_zobrist_keys() -> ZobristKeys {
+    let mut state = 0x706f6d6d65;
+    let mut pieces = [[0; 64]; 13];
+    let mut i = 0;
+    while i < 12 {
+        let mut j = 0;
+        while j < 64 {
+            pieces[i][j] = next_random(&mut state);
+            j += 1;
+        }
+        i += 1;
+    }
+    let black_to_play = next_random(&mut state);
+    let mut castling = [0; 4];
+    let mut i = 0;
+    while i < 4 {
+        castling[i] = next_random(&mut state);
+        i += 1;
+    }
+    let mut en_passant_file = [0; 8];
+    let mut i = 0;
+    while i < 8 {
+        en_passant_file[i] = next_random(&mut state);
+        i += 1;
+    }
+    ZobristKeys {
+        pieces,
+        black_to_play,
+        castling,
+        en_passant_file,
+    }
+}
+
+pub static ZOBRIST_KEYS: ZobristKeys = generate_zobrist_keys();
+
+impl ChessBoard {
+    // hash of everything but the pieces, make_move xors it out before the move and back in after
+    pub(crate) fn get_state_hash(&self) -> u64 {
+        let mut hash = 0;
+        if !self.is_white_to_play {
+            hash ^= ZOBRIST_KEYS.black_to_play;
+        }
+        for (i, has_right) in [
+            self.king_side_castle[1],
+            self.queen_side_castle[1],
+            self.king_side_castle[0],
+            self.queen_side_castle[0],
+        ]
+        .iter()
+        .enumerate()
+        {
+            if *has_right {
+                hash ^= ZOBRIST_KEYS.castling[i];
+            }
+        }
+        if self.can_take_en_passant() {
+            hash ^= ZOBRIST_KEYS.en_passant_file[self.en_passant.trailing_zeros() as usize % 8];
+        }
+        hash
+    }
+
+    // the en-passant square is only part of the position if a pawn of the player to play is next
+    // to the pawn that was just pushed, otherwise the repetitions after a double push would be
+    // missed, the pins aren't looked at as the hash is computed without the attack tables
+    fn can_take_en_passant(&self) -> bool {
+        if self.en_passant == 0 {
+            return false;
+        }
+        let index = self.en_passant.trailing_zeros() as usize;
+        let (pawns, pushed_pawn_index) = if self.is_white_to_play {
+            (self.pieces[TypePiece::WhitePawn as usize], index + 8)
+        } else {
+            (self.pieces[TypePiece::BlackPawn as usize], index - 8)
+        };
+        let file = index % 8;
+        let mut neighbours = 0;
+        if file > 0 {
+            neighbours |= 1 << (pushed_pawn_index - 1);
+        }
+        if file < 7 {
+            neighbours |= 1 << (pushed_pawn_index + 1);
+        }
+        pawns & neighbours != 0
+    }
+
+    // hash of the position computed from scratch, self.hash is updated incrementally by
+    // make_move and must always be equal to it
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = self.get_state_hash();
+        for i in 0..64 {
+            hash ^= ZOBRIST_KEYS.pieces[self.pieces_by_index[i] as usize][i];
+        }
+        hash
+    }
+
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_mask::{MagicSource, MainHashtables, generate_main_hashtables};
+    use crate::chess_move::Move;
+    use crate::get_moves::MovesStruct;
+
+    fn assert_hash_is_incremental(chessboard: &mut ChessBoard, ma: &MainHashtables, depth: u8) {
+        assert_eq!(chessboard.get_hash(), chessboard.compute_hash());
+        if depth == 0 {
+            return;
+        }
+        let mut moves = MovesStruct::init();
+        chessboard.get_moves(ma, &mut moves);
+        for move_code in &moves.moves[..moves.move_number as usize] {
+            let hash = chessboard.get_hash();
+            chessboard.make_move(*move_code);
+            assert_hash_is_incremental(chessboard, ma, depth - 1);
+            chessboard.unmake_move();
+            assert_eq!(chessboard.get_hash(), hash);
+        }
+    }
+
+    #[test]
+    fn hash_is_updated_incrementally() {
+        let ma = generate_main_hashtables(MagicSource::Precomputed);
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mut chessboard = ChessBoard::from_fen(fen).unwrap();
+            assert_hash_is_incremental(&mut chessboard, &ma, 3);
+        }
+    }
+
+    #[test]
+    fn en_passant_is_only_hashed_when_it_can_be_taken() {
+        let hash = |fen: &str| ChessBoard::from_fen(fen).unwrap().get_hash();
+        // no black pawn next to e4
+        assert_eq!(
+            hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+        // exd6 is possible
+        assert_ne!(
+            hash("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"),
+            hash("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3")
+        );
+    }
+
+    #[test]
+    fn transpositions_have_the_same_hash() {
+        let mut first = ChessBoard::from_fen(crate::notation::STARTING_FEN).unwrap();
+        let mut second = ChessBoard::from_fen(crate::notation::STARTING_FEN).unwrap();
+        // Nf3 Nf6 Nc3 and Nc3 Nf6 Nf3
+        for (from_index, to_index) in [(62, 45), (6, 21), (57, 42)] {
+            first.make_move(Move::new(from_index, to_index));
+        }
+        for (from_index, to_index) in [(57, 42), (6, 21), (62, 45)] {
+            second.make_move(Move::new(from_index, to_index));
+        }
+        assert_eq!(first.get_hash(), second.get_hash());
+        assert_ne!(
+            first.get_hash(),
+            ChessBoard::from_fen(crate::notation::STARTING_FEN)
+                .unwrap()
+                .get_hash()
+        );
+    }
+}